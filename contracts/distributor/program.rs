@@ -0,0 +1,511 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+
+declare_id!("WALEDist11111111111111111111111111111111111");
+
+#[program]
+pub mod distributor {
+    use super::*;
+    
+    /// Create a Merkle distributor for `mint`
+    ///
+    /// The tree's leaves are `(index, recipient, amount)` entries hashed
+    /// with `leaf_hash`. Tokens are funded by transferring or minting into
+    /// the distributor's vault; anything unclaimed at `claim_deadline` can
+    /// be clawed back to `clawback_receiver`.
+    pub fn new_distributor(
+        ctx: Context<NewDistributor>,
+        merkle_root: [u8; 32],
+        max_total_claim: u64,
+        max_num_nodes: u64,
+        claim_deadline: i64,
+    ) -> Result<()> {
+        require!(max_total_claim > 0 && max_num_nodes > 0, ErrorCode::InvalidAmount);
+        require!(claim_deadline > Clock::get()?.unix_timestamp, ErrorCode::InvalidDeadline);
+        
+        let distributor = &mut ctx.accounts.distributor;
+        distributor.base = ctx.accounts.base.key();
+        distributor.admin = ctx.accounts.admin.key();
+        distributor.mint = ctx.accounts.mint.key();
+        distributor.vault = ctx.accounts.vault.key();
+        distributor.merkle_root = merkle_root;
+        distributor.max_total_claim = max_total_claim;
+        distributor.max_num_nodes = max_num_nodes;
+        distributor.total_claimed = 0;
+        distributor.num_claimed = 0;
+        distributor.claim_deadline = claim_deadline;
+        distributor.clawback_receiver = ctx.accounts.clawback_receiver.key();
+        distributor.clawed_back = false;
+        distributor.bumps.distributor = *ctx.bumps.get("distributor").unwrap();
+        distributor.bumps.vault = *ctx.bumps.get("vault").unwrap();
+        
+        emit!(DistributorCreatedEvent {
+            distributor: distributor.key(),
+            mint: distributor.mint,
+            merkle_root,
+            max_total_claim,
+            max_num_nodes,
+            claim_deadline,
+        });
+        
+        Ok(())
+    }
+    
+    /// Create the claim bitmap page covering indexes
+    /// `page * ClaimBitmap::BITS_PER_PAGE ..`; anyone may pay for it
+    pub fn create_claim_bitmap(
+        ctx: Context<CreateClaimBitmap>,
+        page: u32,
+    ) -> Result<()> {
+        let distributor = &ctx.accounts.distributor;
+        require!(
+            u64::from(page) * ClaimBitmap::BITS_PER_PAGE < distributor.max_num_nodes,
+            ErrorCode::InvalidBitmapPage
+        );
+        
+        let bitmap = &mut ctx.accounts.claim_bitmap;
+        bitmap.distributor = distributor.key();
+        bitmap.page = page;
+        bitmap.bits = vec![0; ClaimBitmap::BYTES_PER_PAGE];
+        bitmap.bumps.claim_bitmap = *ctx.bumps.get("claim_bitmap").unwrap();
+        
+        Ok(())
+    }
+    
+    /// Claim the entry at `index` by proving it is in the tree
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn claim<'info>(
+        ctx: Context<'_, '_, '_, 'info, Claim<'info>>,
+        index: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let distributor = &ctx.accounts.distributor;
+        let claimant = ctx.accounts.claimant.key();
+        
+        require!(!distributor.clawed_back, ErrorCode::ClaimsClosed);
+        require!(now < distributor.claim_deadline, ErrorCode::ClaimsClosed);
+        require!(index < distributor.max_num_nodes, ErrorCode::InvalidProof);
+        require!(proof.len() <= MAX_PROOF_LEN, ErrorCode::InvalidProof);
+        require!(
+            verify_proof(&proof, distributor.merkle_root, leaf_hash(index, &claimant, amount)),
+            ErrorCode::InvalidProof
+        );
+        
+        ctx.accounts.claim_bitmap.set(index)?;
+        
+        let total_claimed = distributor.total_claimed.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        require!(total_claimed <= distributor.max_total_claim, ErrorCode::ExceededMaxClaim);
+        
+        let seeds = &[
+            b"distributor".as_ref(),
+            distributor.base.as_ref(),
+            &[distributor.bumps.distributor],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.vault.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.claimant_token_account.to_account_info(),
+            authority: distributor.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+        
+        let distributor = &mut ctx.accounts.distributor;
+        distributor.total_claimed = total_claimed;
+        distributor.num_claimed = distributor.num_claimed.checked_add(1)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(ClaimedEvent {
+            distributor: distributor.key(),
+            index,
+            claimant,
+            amount,
+            total_claimed,
+        });
+        
+        Ok(())
+    }
+    
+    /// Send everything left in the vault to the clawback receiver once the
+    /// claim deadline has passed; anyone may crank
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn clawback<'info>(
+        ctx: Context<'_, '_, '_, 'info, Clawback<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let distributor = &ctx.accounts.distributor;
+        
+        require!(!distributor.clawed_back, ErrorCode::AlreadyClawedBack);
+        require!(now >= distributor.claim_deadline, ErrorCode::ClaimsStillOpen);
+        
+        let amount = ctx.accounts.vault.amount;
+        if amount > 0 {
+            let seeds = &[
+                b"distributor".as_ref(),
+                distributor.base.as_ref(),
+                &[distributor.bumps.distributor],
+            ];
+            let signer = &[&seeds[..]];
+            let cpi_accounts = token_interface::TransferChecked {
+                from: ctx.accounts.vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.clawback_receiver.to_account_info(),
+                authority: distributor.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            )
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+            transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+        }
+        
+        let distributor = &mut ctx.accounts.distributor;
+        distributor.clawed_back = true;
+        
+        emit!(ClawbackEvent {
+            distributor: distributor.key(),
+            receiver: distributor.clawback_receiver,
+            amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+}
+
+/// Longest proof accepted, enough for 2^32 leaves
+pub const MAX_PROOF_LEN: usize = 32;
+
+/// Hash of a tree leaf; the leading 0 byte keeps leaves distinct from
+/// inner nodes
+pub fn leaf_hash(index: u64, recipient: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[&[0u8], &index.to_le_bytes(), recipient.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+/// Hash of an inner node; children are sorted so proofs need no
+/// left/right flags
+pub fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[&[1u8], lo, hi]).to_bytes()
+}
+
+/// Check that `leaf` hashes up to `root` through `proof`
+pub fn verify_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    proof.iter().fold(leaf, |node, sibling| node_hash(&node, sibling)) == root
+}
+
+#[derive(Accounts)]
+pub struct NewDistributor<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// Unique key the distributor address is derived from
+    pub base: Signer<'info>,
+    
+    /// CHECK: Only recorded as the distributor's admin
+    pub admin: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = Distributor::space(),
+        seeds = [b"distributor", base.key().as_ref()],
+        bump
+    )]
+    pub distributor: Account<'info, Distributor>,
+    
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"distributor_vault", distributor.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = distributor,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        constraint = clawback_receiver.mint == mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub clawback_receiver: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(page: u32)]
+pub struct CreateClaimBitmap<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"distributor", distributor.base.as_ref()],
+        bump = distributor.bumps.distributor,
+    )]
+    pub distributor: Account<'info, Distributor>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = ClaimBitmap::space(),
+        seeds = [b"claim_bitmap", distributor.key().as_ref(), &page.to_le_bytes()],
+        bump
+    )]
+    pub claim_bitmap: Account<'info, ClaimBitmap>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u64)]
+pub struct Claim<'info> {
+    pub claimant: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"distributor", distributor.base.as_ref()],
+        bump = distributor.bumps.distributor,
+    )]
+    pub distributor: Account<'info, Distributor>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"claim_bitmap",
+            distributor.key().as_ref(),
+            &ClaimBitmap::page_of(index).to_le_bytes(),
+        ],
+        bump = claim_bitmap.bumps.claim_bitmap,
+    )]
+    pub claim_bitmap: Account<'info, ClaimBitmap>,
+    
+    #[account(mut, address = distributor.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = distributor.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = claimant_token_account.owner == claimant.key(),
+        constraint = claimant_token_account.mint == distributor.mint @ ErrorCode::InvalidMint,
+    )]
+    pub claimant_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Clawback<'info> {
+    #[account(
+        mut,
+        seeds = [b"distributor", distributor.base.as_ref()],
+        bump = distributor.bumps.distributor,
+    )]
+    pub distributor: Account<'info, Distributor>,
+    
+    #[account(mut, address = distributor.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = distributor.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut, address = distributor.clawback_receiver)]
+    pub clawback_receiver: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[account]
+#[derive(Default)]
+pub struct Distributor {
+    pub base: Pubkey,                 // Key the distributor address derives from
+    pub admin: Pubkey,                // Account that set up the distribution
+    pub mint: Pubkey,                 // Mint being distributed
+    pub vault: Pubkey,                // PDA token account holding unclaimed tokens
+    pub merkle_root: [u8; 32],        // Root of the (index, recipient, amount) tree
+    pub max_total_claim: u64,         // Sum of all entries in the tree
+    pub max_num_nodes: u64,           // Number of entries in the tree
+    pub total_claimed: u64,           // Tokens claimed so far
+    pub num_claimed: u64,             // Entries claimed so far
+    pub claim_deadline: i64,          // Claims close and clawback opens here
+    pub clawback_receiver: Pubkey,    // Token account unclaimed tokens return to
+    pub clawed_back: bool,            // Whether clawback has run
+    pub bumps: DistributorBumps,      // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct DistributorBumps {
+    pub distributor: u8,
+    pub vault: u8,
+}
+
+/// One page of the distributor's claimed-index bitmap
+#[account]
+#[derive(Default)]
+pub struct ClaimBitmap {
+    pub distributor: Pubkey,          // Distributor the page belongs to
+    pub page: u32,                    // Page number
+    pub bits: Vec<u8>,                // One bit per index, set once claimed
+    pub bumps: ClaimBitmapBumps,      // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ClaimBitmapBumps {
+    pub claim_bitmap: u8,
+}
+
+impl Distributor {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Base
+            32 + // Admin
+            32 + // Mint
+            32 + // Vault
+            32 + // Merkle root
+            8 + // Max total claim
+            8 + // Max num nodes
+            8 + // Total claimed
+            8 + // Num claimed
+            8 + // Claim deadline
+            32 + // Clawback receiver
+            1 + // Clawed back
+            2 // Bumps
+    }
+}
+
+impl ClaimBitmap {
+    pub const BYTES_PER_PAGE: usize = 1024;
+    pub const BITS_PER_PAGE: u64 = (Self::BYTES_PER_PAGE * 8) as u64;
+    
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Distributor
+            4 + // Page
+            4 + Self::BYTES_PER_PAGE + // Bits
+            1 // Bump
+    }
+    
+    pub fn page_of(index: u64) -> u32 {
+        (index / Self::BITS_PER_PAGE) as u32
+    }
+    
+    /// Mark `index` claimed, failing if it already was
+    pub fn set(&mut self, index: u64) -> Result<()> {
+        let bit = (index % Self::BITS_PER_PAGE) as usize;
+        let mask = 1u8 << (bit % 8);
+        let byte = &mut self.bits[bit / 8];
+        require!(*byte & mask == 0, ErrorCode::AlreadyClaimed);
+        *byte |= mask;
+        Ok(())
+    }
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount must be positive")]
+    InvalidAmount,
+    #[msg("Token account mint does not match the distributor")]
+    InvalidMint,
+    #[msg("Numeric overflow occurred")]
+    NumericOverflow,
+    #[msg("Claim deadline must be in the future")]
+    InvalidDeadline,
+    #[msg("Bitmap page is beyond the number of entries")]
+    InvalidBitmapPage,
+    #[msg("Invalid Merkle proof")]
+    InvalidProof,
+    #[msg("Entry has already been claimed")]
+    AlreadyClaimed,
+    #[msg("Claim would exceed the distributor's total")]
+    ExceededMaxClaim,
+    #[msg("Claims are closed")]
+    ClaimsClosed,
+    #[msg("Claim deadline has not passed")]
+    ClaimsStillOpen,
+    #[msg("Unclaimed tokens were already clawed back")]
+    AlreadyClawedBack,
+}
+
+// Events
+#[event]
+pub struct DistributorCreatedEvent {
+    pub distributor: Pubkey,
+    pub mint: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub max_total_claim: u64,
+    pub max_num_nodes: u64,
+    pub claim_deadline: i64,
+}
+
+#[event]
+pub struct ClaimedEvent {
+    pub distributor: Pubkey,
+    pub index: u64,
+    pub claimant: Pubkey,
+    pub amount: u64,
+    pub total_claimed: u64,
+}
+
+#[event]
+pub struct ClawbackEvent {
+    pub distributor: Pubkey,
+    pub receiver: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_hash_is_order_independent() {
+        let a = [1u8; 32];
+        let b = [2u8; 32];
+        assert_eq!(node_hash(&a, &b), node_hash(&b, &a));
+        assert_ne!(node_hash(&a, &b), node_hash(&a, &a));
+    }
+
+    #[test]
+    fn leaf_hash_binds_every_field() {
+        let recipient = Pubkey::new_unique();
+        let leaf = leaf_hash(0, &recipient, 100);
+        assert_ne!(leaf, leaf_hash(1, &recipient, 100));
+        assert_ne!(leaf, leaf_hash(0, &Pubkey::new_unique(), 100));
+        assert_ne!(leaf, leaf_hash(0, &recipient, 101));
+    }
+
+    #[test]
+    fn verify_proof_checks_the_path() {
+        let first = leaf_hash(0, &Pubkey::new_unique(), 1);
+        let second = leaf_hash(1, &Pubkey::new_unique(), 2);
+        let root = node_hash(&first, &second);
+        
+        assert!(verify_proof(&[], first, first));
+        assert!(verify_proof(&[second], root, first));
+        assert!(verify_proof(&[first], root, second));
+        assert!(!verify_proof(&[first], root, first));
+        assert!(!verify_proof(&[], root, first));
+    }
+}
@@ -0,0 +1,1307 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+
+declare_id!("WALEGov111111111111111111111111111111111111");
+
+#[program]
+pub mod governance {
+    use super::*;
+    
+    /// Create the WALE governance realm
+    ///
+    /// The realm's `authority` PDA is meant to become `ProtocolConfig.admin`
+    /// in `mcp` and `TokenInfo.authority` in `wale_token`, so admin actions
+    /// can only happen through executed proposals.
+    pub fn initialize_realm(
+        ctx: Context<InitializeRealm>,
+        config: GovernanceConfig,
+    ) -> Result<()> {
+        config.validate()?;
+        
+        let realm = &mut ctx.accounts.realm;
+        realm.wale_mint = ctx.accounts.wale_mint.key();
+        realm.stake_vault = ctx.accounts.stake_vault.key();
+        realm.config = config;
+        realm.total_staked = 0;
+        realm.proposal_count = 0;
+        realm.open_votes = Vec::new();
+        realm.bumps.realm = *ctx.bumps.get("realm").unwrap();
+        realm.bumps.stake_vault = *ctx.bumps.get("stake_vault").unwrap();
+        realm.bumps.authority = *ctx.bumps.get("authority").unwrap();
+        
+        emit!(RealmConfigUpdatedEvent {
+            realm: realm.key(),
+            config: realm.config.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+    
+    /// Change realm parameters; only callable by the realm authority, i.e.
+    /// through an executed proposal
+    pub fn update_governance_config(
+        ctx: Context<UpdateGovernanceConfig>,
+        config: GovernanceConfig,
+    ) -> Result<()> {
+        config.validate()?;
+        
+        let realm = &mut ctx.accounts.realm;
+        realm.config = config;
+        
+        emit!(RealmConfigUpdatedEvent {
+            realm: realm.key(),
+            config: realm.config.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+    
+    /// Stake WALE to gain voting power
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn stake<'info>(
+        ctx: Context<'_, '_, '_, 'info, Stake<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        
+        let vault_before = ctx.accounts.stake_vault.amount;
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.owner_token_account.to_account_info(),
+            mint: ctx.accounts.wale_mint.to_account_info(),
+            to: ctx.accounts.stake_vault.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.wale_mint.decimals)?;
+        // Transfer fees come out of the deposit; stake what arrived
+        ctx.accounts.stake_vault.reload()?;
+        let amount = ctx.accounts.stake_vault.amount - vault_before;
+        
+        let clock = Clock::get()?;
+        let realm = &mut ctx.accounts.realm;
+        let stake = &mut ctx.accounts.stake;
+        if stake.owner == Pubkey::default() {
+            stake.realm = realm.key();
+            stake.owner = ctx.accounts.owner.key();
+            stake.delegate = None;
+            stake.bumps.stake = *ctx.bumps.get("stake").unwrap();
+        }
+        stake.amount = stake.amount.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        realm.total_staked = realm.total_staked.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        // Voting power goes to the delegate, if any, else to the owner
+        let weight = &mut ctx.accounts.voter_weight;
+        weight.init_if_new(realm.key(), stake.voting_key(&ctx.accounts.owner.key()), *ctx.bumps.get("voter_weight").unwrap());
+        weight.add(clock.slot, amount, &realm.open_snapshots(clock.unix_timestamp))?;
+        
+        emit!(StakeChangedEvent {
+            realm: realm.key(),
+            owner: stake.owner,
+            amount: stake.amount,
+            total_staked: realm.total_staked,
+        });
+        
+        Ok(())
+    }
+    
+    /// Withdraw staked WALE
+    ///
+    /// Votes are weighed at each proposal's snapshot slot, so unstaking
+    /// doesn't change votes already cast and moved tokens can't vote twice.
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn unstake<'info>(
+        ctx: Context<'_, '_, '_, 'info, Unstake<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(amount <= ctx.accounts.stake.amount, ErrorCode::InsufficientStake);
+        
+        let realm = &ctx.accounts.realm;
+        let seeds = &[b"realm".as_ref(), realm.wale_mint.as_ref(), &[realm.bumps.realm]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.stake_vault.to_account_info(),
+            mint: ctx.accounts.wale_mint.to_account_info(),
+            to: ctx.accounts.owner_token_account.to_account_info(),
+            authority: realm.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.wale_mint.decimals)?;
+        
+        let clock = Clock::get()?;
+        let realm = &mut ctx.accounts.realm;
+        let stake = &mut ctx.accounts.stake;
+        stake.amount -= amount;
+        realm.total_staked -= amount;
+        ctx.accounts.voter_weight.sub(clock.slot, amount, &realm.open_snapshots(clock.unix_timestamp))?;
+        
+        emit!(StakeChangedEvent {
+            realm: realm.key(),
+            owner: stake.owner,
+            amount: stake.amount,
+            total_staked: realm.total_staked,
+        });
+        
+        Ok(())
+    }
+    
+    /// Create a proposal carrying the instructions to run if it passes
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        description_hash: [u8; 32],
+        instructions: Vec<ProposalInstruction>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp;
+        let realm = &mut ctx.accounts.realm;
+        let proposal = &mut ctx.accounts.proposal;
+        
+        require!(
+            ctx.accounts.voter_weight.current_power() >= realm.config.proposal_threshold,
+            ErrorCode::BelowProposalThreshold
+        );
+        ProposalInstruction::validate(&instructions)?;
+        
+        proposal.realm = realm.key();
+        proposal.id = realm.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.description_hash = description_hash;
+        proposal.instructions = instructions;
+        proposal.state = ProposalState::Voting;
+        proposal.for_votes = 0;
+        proposal.against_votes = 0;
+        proposal.total_staked_snapshot = realm.total_staked;
+        // Power is read from the slot before creation so nothing staked or
+        // delegated in the creating slot counts
+        proposal.snapshot_slot = clock.slot.saturating_sub(1);
+        proposal.quorum_bps = realm.config.quorum_bps;
+        proposal.approval_threshold_bps = realm.config.approval_threshold_bps;
+        proposal.created_at = now;
+        proposal.voting_ends_at = now.checked_add(realm.config.voting_period)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        realm.open_vote(proposal.snapshot_slot, proposal.voting_ends_at, now)?;
+        proposal.eta = 0;
+        proposal.executed_at = 0;
+        proposal.bumps.proposal = *ctx.bumps.get("proposal").unwrap();
+        
+        realm.proposal_count = realm.proposal_count.checked_add(1)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(ProposalCreatedEvent {
+            realm: realm.key(),
+            proposal: proposal.key(),
+            id: proposal.id,
+            proposer: proposal.proposer,
+            description_hash,
+            voting_ends_at: proposal.voting_ends_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Cast a vote with the voter's power at the proposal's snapshot slot
+    ///
+    /// Power is the voter's own stake unless delegated away, plus stake
+    /// delegated to them.
+    pub fn cast_vote(
+        ctx: Context<CastVote>,
+        support: bool,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let proposal = &mut ctx.accounts.proposal;
+        let vote = &mut ctx.accounts.vote;
+        
+        require!(proposal.state == ProposalState::Voting, ErrorCode::ProposalNotVoting);
+        require!(now < proposal.voting_ends_at, ErrorCode::VotingClosed);
+        
+        let weight = ctx.accounts.voter_weight.power_at(proposal.snapshot_slot)?;
+        require!(weight > 0, ErrorCode::InsufficientStake);
+        
+        if support {
+            proposal.for_votes = proposal.for_votes.checked_add(weight)
+                .ok_or(ErrorCode::NumericOverflow)?;
+        } else {
+            proposal.against_votes = proposal.against_votes.checked_add(weight)
+                .ok_or(ErrorCode::NumericOverflow)?;
+        }
+        
+        vote.proposal = proposal.key();
+        vote.voter = ctx.accounts.voter.key();
+        vote.support = support;
+        vote.weight = weight;
+        vote.voted_at = now;
+        vote.bumps.vote = *ctx.bumps.get("vote").unwrap();
+        
+        emit!(VoteCastEvent {
+            proposal: proposal.key(),
+            voter: vote.voter,
+            support,
+            weight,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+    
+    /// Tally a proposal once voting has ended and queue it behind the
+    /// timelock if it passed
+    pub fn finalize_proposal(
+        ctx: Context<FinalizeProposal>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let realm = &ctx.accounts.realm;
+        let proposal = &mut ctx.accounts.proposal;
+        
+        require!(proposal.state == ProposalState::Voting, ErrorCode::ProposalNotVoting);
+        require!(now >= proposal.voting_ends_at, ErrorCode::VotingNotEnded);
+        
+        if proposal.passed() {
+            proposal.state = ProposalState::Queued;
+            proposal.eta = now.checked_add(realm.config.timelock_delay)
+                .ok_or(ErrorCode::NumericOverflow)?;
+        } else {
+            proposal.state = ProposalState::Defeated;
+        }
+        
+        emit!(ProposalFinalizedEvent {
+            proposal: proposal.key(),
+            state: proposal.state,
+            for_votes: proposal.for_votes,
+            against_votes: proposal.against_votes,
+            eta: proposal.eta,
+        });
+        
+        Ok(())
+    }
+    
+    /// Execute a queued proposal after its timelock
+    ///
+    /// `remaining_accounts` must contain every account referenced by the
+    /// proposal's instructions, including the target programs. The realm
+    /// authority PDA signs each CPI.
+    pub fn execute_proposal<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteProposal<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let realm = &ctx.accounts.realm;
+        let proposal = &mut ctx.accounts.proposal;
+        
+        require!(proposal.state == ProposalState::Queued, ErrorCode::ProposalNotQueued);
+        require!(now >= proposal.eta, ErrorCode::TimelockNotElapsed);
+        
+        let realm_key = realm.key();
+        let seeds = &[b"authority".as_ref(), realm_key.as_ref(), &[realm.bumps.authority]];
+        let signer = &[&seeds[..]];
+        
+        // Proposals may call this program, so mark the proposal executed
+        // and persist that before any CPI can re-enter with it
+        proposal.state = ProposalState::Executed;
+        proposal.executed_at = now;
+        proposal.exit(&crate::ID)?;
+        
+        for ix in proposal.instructions.iter() {
+            let instruction = Instruction {
+                program_id: ix.program_id,
+                accounts: ix.accounts.iter()
+                    .map(|meta| AccountMeta {
+                        pubkey: meta.pubkey,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect(),
+                data: ix.data.clone(),
+            };
+            invoke_signed(&instruction, ctx.remaining_accounts, signer)?;
+        }
+        
+        emit!(ProposalExecutedEvent {
+            proposal: proposal.key(),
+            instruction_count: proposal.instructions.len() as u8,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+    
+    /// Delegate all of the owner's staked voting power to `delegate`
+    ///
+    /// Delegation is one level deep: power delegated to an account can't
+    /// be passed on by that account.
+    pub fn delegate(
+        ctx: Context<Delegate>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let slot = clock.slot;
+        let realm_key = ctx.accounts.realm.key();
+        let open = ctx.accounts.realm.open_snapshots(clock.unix_timestamp);
+        let stake = &mut ctx.accounts.stake;
+        let delegate = ctx.accounts.delegate.key();
+        
+        require!(stake.delegate.is_none(), ErrorCode::AlreadyDelegated);
+        require!(delegate != stake.owner, ErrorCode::InvalidDelegate);
+        
+        ctx.accounts.owner_weight.sub(slot, stake.amount, &open)?;
+        let delegate_weight = &mut ctx.accounts.delegate_weight;
+        delegate_weight.init_if_new(realm_key, delegate, *ctx.bumps.get("delegate_weight").unwrap());
+        delegate_weight.add(slot, stake.amount, &open)?;
+        stake.delegate = Some(delegate);
+        
+        emit!(DelegationChangedEvent {
+            realm: realm_key,
+            owner: stake.owner,
+            delegate: stake.delegate,
+            amount: stake.amount,
+            slot,
+        });
+        
+        Ok(())
+    }
+    
+    /// Take delegated voting power back
+    pub fn undelegate(
+        ctx: Context<Undelegate>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let slot = clock.slot;
+        let open = ctx.accounts.realm.open_snapshots(clock.unix_timestamp);
+        let stake = &mut ctx.accounts.stake;
+        
+        ctx.accounts.delegate_weight.sub(slot, stake.amount, &open)?;
+        ctx.accounts.owner_weight.add(slot, stake.amount, &open)?;
+        stake.delegate = None;
+        
+        emit!(DelegationChangedEvent {
+            realm: ctx.accounts.realm.key(),
+            owner: stake.owner,
+            delegate: None,
+            amount: stake.amount,
+            slot,
+        });
+        
+        Ok(())
+    }
+    
+    /// Withdraw a proposal while it is still being voted on
+    pub fn cancel_proposal(
+        ctx: Context<CancelProposal>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        
+        require!(proposal.state == ProposalState::Voting, ErrorCode::ProposalNotVoting);
+        proposal.state = ProposalState::Cancelled;
+        ctx.accounts.realm.close_vote(proposal.snapshot_slot, proposal.voting_ends_at);
+        
+        emit!(ProposalFinalizedEvent {
+            proposal: proposal.key(),
+            state: proposal.state,
+            for_votes: proposal.for_votes,
+            against_votes: proposal.against_votes,
+            eta: 0,
+        });
+        
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeRealm<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = Realm::space(),
+        seeds = [b"realm", wale_mint.key().as_ref()],
+        bump
+    )]
+    pub realm: Account<'info, Realm>,
+    
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"stake_vault", realm.key().as_ref()],
+        bump,
+        token::mint = wale_mint,
+        token::authority = realm,
+    )]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: PDA that signs executed proposals, holds no data
+    #[account(
+        seeds = [b"authority", realm.key().as_ref()],
+        bump
+    )]
+    pub authority: UncheckedAccount<'info>,
+    
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGovernanceConfig<'info> {
+    #[account(
+        seeds = [b"authority", realm.key().as_ref()],
+        bump = realm.bumps.authority,
+    )]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"realm", realm.wale_mint.as_ref()],
+        bump = realm.bumps.realm,
+    )]
+    pub realm: Account<'info, Realm>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"realm", realm.wale_mint.as_ref()],
+        bump = realm.bumps.realm,
+    )]
+    pub realm: Account<'info, Realm>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = StakeAccount::space(),
+        seeds = [b"stake", realm.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub stake: Account<'info, StakeAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = VoterWeight::space(),
+        seeds = [b"voter_weight", realm.key().as_ref(), stake.voting_key(&owner.key()).as_ref()],
+        bump
+    )]
+    pub voter_weight: Account<'info, VoterWeight>,
+    
+    #[account(mut, address = realm.stake_vault)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = realm.wale_mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key(),
+        constraint = owner_token_account.mint == realm.wale_mint @ ErrorCode::InvalidMint,
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"realm", realm.wale_mint.as_ref()],
+        bump = realm.bumps.realm,
+    )]
+    pub realm: Account<'info, Realm>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", realm.key().as_ref(), owner.key().as_ref()],
+        bump = stake.bumps.stake,
+    )]
+    pub stake: Account<'info, StakeAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"voter_weight", realm.key().as_ref(), stake.voting_key(&owner.key()).as_ref()],
+        bump = voter_weight.bumps.voter_weight,
+    )]
+    pub voter_weight: Account<'info, VoterWeight>,
+    
+    #[account(mut, address = realm.stake_vault)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = realm.wale_mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key(),
+        constraint = owner_token_account.mint == realm.wale_mint @ ErrorCode::InvalidMint,
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"realm", realm.wale_mint.as_ref()],
+        bump = realm.bumps.realm,
+    )]
+    pub realm: Account<'info, Realm>,
+    
+    #[account(
+        seeds = [b"voter_weight", realm.key().as_ref(), proposer.key().as_ref()],
+        bump = voter_weight.bumps.voter_weight,
+    )]
+    pub voter_weight: Account<'info, VoterWeight>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = Proposal::space(),
+        seeds = [b"proposal", realm.key().as_ref(), &realm.proposal_count.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.realm.as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bumps.proposal,
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        seeds = [b"voter_weight", proposal.realm.as_ref(), voter.key().as_ref()],
+        bump = voter_weight.bumps.voter_weight,
+    )]
+    pub voter_weight: Account<'info, VoterWeight>,
+    
+    #[account(
+        init,
+        payer = voter,
+        space = VoteRecord::space(),
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote: Account<'info, VoteRecord>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeProposal<'info> {
+    #[account(
+        seeds = [b"realm", realm.wale_mint.as_ref()],
+        bump = realm.bumps.realm,
+    )]
+    pub realm: Account<'info, Realm>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", realm.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bumps.proposal,
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(
+        seeds = [b"realm", realm.wale_mint.as_ref()],
+        bump = realm.bumps.realm,
+    )]
+    pub realm: Account<'info, Realm>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", realm.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bumps.proposal,
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct Delegate<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        seeds = [b"realm", realm.wale_mint.as_ref()],
+        bump = realm.bumps.realm,
+    )]
+    pub realm: Account<'info, Realm>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", realm.key().as_ref(), owner.key().as_ref()],
+        bump = stake.bumps.stake,
+    )]
+    pub stake: Account<'info, StakeAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"voter_weight", realm.key().as_ref(), owner.key().as_ref()],
+        bump = owner_weight.bumps.voter_weight,
+    )]
+    pub owner_weight: Account<'info, VoterWeight>,
+    
+    /// CHECK: Any account can receive delegated power
+    pub delegate: UncheckedAccount<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = VoterWeight::space(),
+        seeds = [b"voter_weight", realm.key().as_ref(), delegate.key().as_ref()],
+        bump
+    )]
+    pub delegate_weight: Account<'info, VoterWeight>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Undelegate<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        seeds = [b"realm", realm.wale_mint.as_ref()],
+        bump = realm.bumps.realm,
+    )]
+    pub realm: Account<'info, Realm>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", realm.key().as_ref(), owner.key().as_ref()],
+        bump = stake.bumps.stake,
+        constraint = stake.delegate.is_some() @ ErrorCode::NotDelegated,
+    )]
+    pub stake: Account<'info, StakeAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"voter_weight", realm.key().as_ref(), owner.key().as_ref()],
+        bump = owner_weight.bumps.voter_weight,
+    )]
+    pub owner_weight: Account<'info, VoterWeight>,
+    
+    #[account(
+        mut,
+        seeds = [b"voter_weight", realm.key().as_ref(), stake.voting_key(&owner.key()).as_ref()],
+        bump = delegate_weight.bumps.voter_weight,
+    )]
+    pub delegate_weight: Account<'info, VoterWeight>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    pub proposer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"realm", realm.wale_mint.as_ref()],
+        bump = realm.bumps.realm,
+    )]
+    pub realm: Account<'info, Realm>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", realm.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bumps.proposal,
+        constraint = proposal.proposer == proposer.key() @ ErrorCode::UnauthorizedProposer,
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+/// Tunable realm parameters
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct GovernanceConfig {
+    pub proposal_threshold: u64,      // Stake needed to create a proposal
+    pub voting_period: i64,           // Seconds a proposal is open for votes
+    pub timelock_delay: i64,          // Seconds between passing and execution
+    pub quorum_bps: u16,              // Share of total stake that must vote
+    pub approval_threshold_bps: u16,  // Share of cast votes that must be in favour
+}
+
+impl GovernanceConfig {
+    pub const MAX_BPS: u16 = 10_000;
+    
+    pub fn space() -> usize {
+        8 + // Proposal threshold
+            8 + // Voting period
+            8 + // Timelock delay
+            2 + // Quorum
+            2 // Approval threshold
+    }
+    
+    pub fn validate(&self) -> Result<()> {
+        require!(self.voting_period > 0, ErrorCode::InvalidGovernanceConfig);
+        require!(self.timelock_delay >= 0, ErrorCode::InvalidGovernanceConfig);
+        require!(self.quorum_bps <= Self::MAX_BPS, ErrorCode::InvalidGovernanceConfig);
+        require!(
+            self.approval_threshold_bps > 0 && self.approval_threshold_bps <= Self::MAX_BPS,
+            ErrorCode::InvalidGovernanceConfig
+        );
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct Realm {
+    pub wale_mint: Pubkey,            // Governance token mint
+    pub stake_vault: Pubkey,          // Vault holding staked WALE
+    pub config: GovernanceConfig,     // Voting parameters
+    pub total_staked: u64,            // WALE currently staked
+    pub proposal_count: u64,          // Proposals created, also the next id
+    pub open_votes: Vec<OpenVote>,    // Snapshots of proposals still being voted on
+    pub bumps: RealmBumps,            // PDA bumps
+}
+
+/// Snapshot slot of a proposal that is open for votes
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpenVote {
+    pub snapshot_slot: u64,
+    pub voting_ends_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct RealmBumps {
+    pub realm: u8,
+    pub stake_vault: u8,
+    pub authority: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct StakeAccount {
+    pub realm: Pubkey,                // Realm the stake belongs to
+    pub owner: Pubkey,                // Staker's public key
+    pub amount: u64,                  // WALE staked
+    pub delegate: Option<Pubkey>,     // Account voting with this stake, if delegated
+    pub bumps: StakeBumps,            // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct StakeBumps {
+    pub stake: u8,
+}
+
+/// Voting power from `slot` onwards
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub slot: u64,
+    pub power: u64,
+}
+
+/// Checkpointed voting power of one voter: own stake unless delegated
+/// away, plus stake delegated to them
+#[account]
+#[derive(Default)]
+pub struct VoterWeight {
+    pub realm: Pubkey,                // Realm the power belongs to
+    pub voter: Pubkey,                // Voter's public key
+    pub checkpoints: Vec<Checkpoint>, // Power history, oldest first
+    pub truncated: bool,              // Whether old checkpoints were dropped
+    pub bumps: VoterWeightBumps,      // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct VoterWeightBumps {
+    pub voter_weight: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProposalState {
+    #[default]
+    Voting,
+    Defeated,
+    Queued,
+    Executed,
+    Cancelled,
+}
+
+
+/// Serialized account meta for a proposal instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ProposalAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// An instruction a proposal runs when executed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ProposalInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<ProposalAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl ProposalInstruction {
+    pub const MAX_INSTRUCTIONS: usize = 4;
+    pub const MAX_ACCOUNTS: usize = 8;
+    pub const MAX_DATA_LEN: usize = 256;
+    
+    pub fn space() -> usize {
+        32 + // Program id
+            4 + Self::MAX_ACCOUNTS * (32 + 1 + 1) + // Accounts (allocate max)
+            4 + Self::MAX_DATA_LEN // Data (allocate max)
+    }
+    
+    /// Proposals may only call the admin surface of the WALE programs
+    pub fn validate(instructions: &[ProposalInstruction]) -> Result<()> {
+        require!(!instructions.is_empty(), ErrorCode::InvalidProposalInstructions);
+        require!(
+            instructions.len() <= Self::MAX_INSTRUCTIONS,
+            ErrorCode::InvalidProposalInstructions
+        );
+        for ix in instructions {
+            require!(
+                ix.program_id == mcp::ID || ix.program_id == wale_token::ID || ix.program_id == crate::ID,
+                ErrorCode::UnsupportedProposalTarget
+            );
+            require!(ix.accounts.len() <= Self::MAX_ACCOUNTS, ErrorCode::InvalidProposalInstructions);
+            require!(ix.data.len() <= Self::MAX_DATA_LEN, ErrorCode::InvalidProposalInstructions);
+        }
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct Proposal {
+    pub realm: Pubkey,                // Realm the proposal belongs to
+    pub id: u64,                      // Sequential proposal id
+    pub proposer: Pubkey,             // Proposer's public key
+    pub description_hash: [u8; 32],   // SHA-256 of the off-chain description
+    pub instructions: Vec<ProposalInstruction>, // Instructions run on execution
+    pub state: ProposalState,         // Lifecycle state
+    pub for_votes: u64,               // Stake voting in favour
+    pub against_votes: u64,           // Stake voting against
+    pub total_staked_snapshot: u64,   // Total stake when the proposal was created
+    pub snapshot_slot: u64,           // Slot voting power is measured at
+    pub quorum_bps: u16,              // Quorum at creation
+    pub approval_threshold_bps: u16,  // Approval threshold at creation
+    pub created_at: i64,              // Creation timestamp
+    pub voting_ends_at: i64,          // End of the voting period
+    pub eta: i64,                     // Earliest execution time once queued
+    pub executed_at: i64,             // Execution timestamp
+    pub bumps: ProposalBumps,         // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ProposalBumps {
+    pub proposal: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct VoteRecord {
+    pub proposal: Pubkey,             // Proposal voted on
+    pub voter: Pubkey,                // Voter's public key
+    pub support: bool,                // In favour or against
+    pub weight: u64,                  // Voting power used
+    pub voted_at: i64,                // Vote timestamp
+    pub bumps: VoteBumps,             // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct VoteBumps {
+    pub vote: u8,
+}
+
+impl Realm {
+    pub const MAX_OPEN_VOTES: usize = 16;
+
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // WALE mint
+            32 + // Stake vault
+            GovernanceConfig::space() + // Config
+            8 + // Total staked
+            8 + // Proposal count
+            4 + Self::MAX_OPEN_VOTES * (8 + 8) + // Open votes (allocate max)
+            3 // Bumps
+    }
+
+    /// Snapshot slots that votes may still be cast at
+    pub fn open_snapshots(&self, now: i64) -> Vec<u64> {
+        self.open_votes.iter()
+            .filter(|vote| now < vote.voting_ends_at)
+            .map(|vote| vote.snapshot_slot)
+            .collect()
+    }
+
+    fn open_vote(&mut self, snapshot_slot: u64, voting_ends_at: i64, now: i64) -> Result<()> {
+        self.open_votes.retain(|vote| now < vote.voting_ends_at);
+        require!(self.open_votes.len() < Self::MAX_OPEN_VOTES, ErrorCode::TooManyOpenProposals);
+        self.open_votes.push(OpenVote { snapshot_slot, voting_ends_at });
+        Ok(())
+    }
+
+    fn close_vote(&mut self, snapshot_slot: u64, voting_ends_at: i64) {
+        let closed = OpenVote { snapshot_slot, voting_ends_at };
+        if let Some(i) = self.open_votes.iter().position(|vote| *vote == closed) {
+            self.open_votes.remove(i);
+        }
+    }
+}
+
+impl StakeAccount {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Realm
+            32 + // Owner
+            8 + // Amount
+            1 + 32 + // Delegate
+            1 // Bump
+    }
+
+    /// Account whose voting power includes `owner`'s stake
+    ///
+    /// `owner` is passed in rather than read from the account, which is
+    /// still blank while `init_if_needed` creates it on a first stake.
+    pub fn voting_key(&self, owner: &Pubkey) -> Pubkey {
+        self.delegate.unwrap_or(*owner)
+    }
+}
+
+impl VoterWeight {
+    pub const MAX_CHECKPOINTS: usize = 32;
+
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Realm
+            32 + // Voter
+            4 + Self::MAX_CHECKPOINTS * (8 + 8) + // Checkpoints (allocate max)
+            1 + // Truncated
+            1 // Bump
+    }
+
+    fn init_if_new(&mut self, realm: Pubkey, voter: Pubkey, bump: u8) {
+        if self.voter == Pubkey::default() {
+            self.realm = realm;
+            self.voter = voter;
+            self.bumps.voter_weight = bump;
+        }
+    }
+
+    pub fn current_power(&self) -> u64 {
+        self.checkpoints.last().map_or(0, |checkpoint| checkpoint.power)
+    }
+
+    /// Voting power as of the end of `slot`
+    pub fn power_at(&self, slot: u64) -> Result<u64> {
+        match self.checkpoints.iter().rev().find(|checkpoint| checkpoint.slot <= slot) {
+            Some(checkpoint) => Ok(checkpoint.power),
+            // Older history was dropped, so the power at `slot` is unknown
+            None if self.truncated => err!(ErrorCode::CheckpointUnavailable),
+            None => Ok(0),
+        }
+    }
+
+    pub fn add(&mut self, slot: u64, amount: u64, open: &[u64]) -> Result<()> {
+        let power = self.current_power().checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        self.write(slot, power, open)
+    }
+
+    pub fn sub(&mut self, slot: u64, amount: u64, open: &[u64]) -> Result<()> {
+        let power = self.current_power().checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientStake)?;
+        self.write(slot, power, open)
+    }
+
+    /// Record `power` at `slot`, overwriting a checkpoint from the same slot
+    ///
+    /// When full, the oldest checkpoint that no open snapshot in `open`
+    /// reads is dropped. Others can move power to or from any voter, so the
+    /// history a pending vote relies on must never be what gets evicted.
+    fn write(&mut self, slot: u64, power: u64, open: &[u64]) -> Result<()> {
+        if let Some(last) = self.checkpoints.last_mut() {
+            if last.slot == slot {
+                last.power = power;
+                return Ok(());
+            }
+        }
+        self.checkpoints.push(Checkpoint { slot, power });
+        if self.checkpoints.len() > Self::MAX_CHECKPOINTS {
+            let evict = self.evictable(open).ok_or(ErrorCode::CheckpointUnavailable)?;
+            self.checkpoints.remove(evict);
+            if evict == 0 {
+                self.truncated = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Index of the oldest checkpoint no open snapshot reads, keeping the
+    /// two newest, which the current and the previous slot read
+    fn evictable(&self, open: &[u64]) -> Option<usize> {
+        let len = self.checkpoints.len();
+        (0..len.saturating_sub(2)).find(|&i| {
+            let from = self.checkpoints[i].slot;
+            let until = self.checkpoints[i + 1].slot;
+            !open.iter().any(|&snapshot| (i == 0 || from <= snapshot) && snapshot < until)
+        })
+    }
+}
+
+// Every open snapshot pins at most one checkpoint, so a full history always
+// has one to evict
+const _: () = assert!(VoterWeight::MAX_CHECKPOINTS > Realm::MAX_OPEN_VOTES + 2);
+
+impl Proposal {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Realm
+            8 + // Id
+            32 + // Proposer
+            32 + // Description hash
+            4 + ProposalInstruction::MAX_INSTRUCTIONS * ProposalInstruction::space() + // Instructions
+            1 + // State
+            8 + // For votes
+            8 + // Against votes
+            8 + // Total staked snapshot
+            8 + // Snapshot slot
+            2 + // Quorum
+            2 + // Approval threshold
+            8 + // Created at
+            8 + // Voting ends at
+            8 + // ETA
+            8 + // Executed at
+            1 // Bump
+    }
+    
+    /// Quorum is measured against total stake at creation, approval
+    /// against votes cast
+    pub fn passed(&self) -> bool {
+        let cast = u128::from(self.for_votes) + u128::from(self.against_votes);
+        let quorum = u128::from(self.total_staked_snapshot) * u128::from(self.quorum_bps)
+            / u128::from(GovernanceConfig::MAX_BPS);
+        if cast == 0 || cast < quorum {
+            return false;
+        }
+        u128::from(self.for_votes) * u128::from(GovernanceConfig::MAX_BPS)
+            >= cast * u128::from(self.approval_threshold_bps)
+    }
+}
+
+impl VoteRecord {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Proposal
+            32 + // Voter
+            1 + // Support
+            8 + // Weight
+            8 + // Voted at
+            1 // Bump
+    }
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount must be positive")]
+    InvalidAmount,
+    #[msg("Token account mint does not match the realm")]
+    InvalidMint,
+    #[msg("Numeric overflow occurred")]
+    NumericOverflow,
+    #[msg("Invalid governance configuration")]
+    InvalidGovernanceConfig,
+    #[msg("Insufficient stake")]
+    InsufficientStake,
+    #[msg("Stake is below the proposal threshold")]
+    BelowProposalThreshold,
+    #[msg("Proposal instructions are empty or too large")]
+    InvalidProposalInstructions,
+    #[msg("Proposals may only call mcp, wale_token or governance")]
+    UnsupportedProposalTarget,
+    #[msg("Proposal is not open for voting")]
+    ProposalNotVoting,
+    #[msg("Voting period has ended")]
+    VotingClosed,
+    #[msg("Voting period has not ended")]
+    VotingNotEnded,
+    #[msg("Proposal is not queued for execution")]
+    ProposalNotQueued,
+    #[msg("Timelock has not elapsed")]
+    TimelockNotElapsed,
+    #[msg("Signer is not the proposer")]
+    UnauthorizedProposer,
+    #[msg("Stake is already delegated, undelegate first")]
+    AlreadyDelegated,
+    #[msg("Stake is not delegated")]
+    NotDelegated,
+    #[msg("Cannot delegate to yourself")]
+    InvalidDelegate,
+    #[msg("Voting power history for the snapshot slot is no longer available")]
+    CheckpointUnavailable,
+    #[msg("Too many proposals are open for voting")]
+    TooManyOpenProposals,
+}
+
+// Events
+#[event]
+pub struct RealmConfigUpdatedEvent {
+    pub realm: Pubkey,
+    pub config: GovernanceConfig,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeChangedEvent {
+    pub realm: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct ProposalCreatedEvent {
+    pub realm: Pubkey,
+    pub proposal: Pubkey,
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub description_hash: [u8; 32],
+    pub voting_ends_at: i64,
+}
+
+#[event]
+pub struct VoteCastEvent {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub support: bool,
+    pub weight: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalFinalizedEvent {
+    pub proposal: Pubkey,
+    pub state: ProposalState,
+    pub for_votes: u64,
+    pub against_votes: u64,
+    pub eta: i64,
+}
+
+#[event]
+pub struct ProposalExecutedEvent {
+    pub proposal: Pubkey,
+    pub instruction_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DelegationChangedEvent {
+    pub realm: Pubkey,
+    pub owner: Pubkey,
+    pub delegate: Option<Pubkey>,
+    pub amount: u64,
+    pub slot: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dust_writes_keep_history_of_open_snapshots() {
+        let mut weight = VoterWeight::default();
+        weight.add(10, 1_000, &[]).unwrap();
+        weight.add(20, 500, &[]).unwrap();
+        let open = [15, 25];
+        
+        // Someone else moving dust in and out every slot
+        for slot in 30..200 {
+            if slot % 2 == 0 {
+                weight.add(slot, 1, &open).unwrap();
+            } else {
+                weight.sub(slot, 1, &open).unwrap();
+            }
+        }
+        
+        assert!(weight.checkpoints.len() <= VoterWeight::MAX_CHECKPOINTS);
+        assert_eq!(weight.power_at(15).unwrap(), 1_000);
+        assert_eq!(weight.power_at(25).unwrap(), 1_500);
+        assert_eq!(weight.current_power(), 1_500);
+    }
+
+    #[test]
+    fn full_history_without_open_votes_drops_the_oldest() {
+        let mut weight = VoterWeight::default();
+        for slot in 1..=(VoterWeight::MAX_CHECKPOINTS as u64 + 1) {
+            weight.add(slot, 1, &[]).unwrap();
+        }
+        
+        assert!(weight.truncated);
+        assert_eq!(weight.checkpoints[0].slot, 2);
+        assert!(weight.power_at(1).is_err());
+        assert_eq!(weight.power_at(2).unwrap(), 2);
+    }
+
+    fn weight_address(realm: &Pubkey, voter: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"voter_weight", realm.as_ref(), voter.as_ref()],
+            &crate::ID,
+        ).0
+    }
+
+    #[test]
+    fn first_stake_votes_and_unstakes_from_the_owners_weight() {
+        let realm = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        
+        // `Stake` derives the weight before `stake` is written
+        let blank = StakeAccount::default();
+        let staked_to = weight_address(&realm, &blank.voting_key(&owner));
+        assert_eq!(staked_to, weight_address(&realm, &owner));
+        
+        let stake = StakeAccount { realm, owner, amount: 1_000, ..Default::default() };
+        // `CastVote` and `Unstake` read the same account
+        assert_eq!(weight_address(&realm, &stake.voting_key(&owner)), staked_to);
+        
+        let mut weight = VoterWeight::default();
+        weight.init_if_new(realm, blank.voting_key(&owner), 255);
+        assert_eq!(weight.voter, owner);
+        weight.add(10, stake.amount, &[]).unwrap();
+        assert_eq!(weight.power_at(10).unwrap(), 1_000);
+        weight.sub(20, stake.amount, &[10]).unwrap();
+        assert_eq!(weight.current_power(), 0);
+        assert_eq!(weight.power_at(10).unwrap(), 1_000);
+    }
+}
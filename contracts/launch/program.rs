@@ -0,0 +1,1033 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+
+declare_id!("WALELnch11111111111111111111111111111111111");
+
+#[program]
+pub mod launch {
+    use super::*;
+    
+    /// Create a fair-launch sale and deposit every round's allocation
+    ///
+    /// Payments are in `payment_mint`; SOL sales use the wrapped SOL mint.
+    /// Proceeds stay in the launch until it is finalized. Either mint may
+    /// live under either token program; a sale mint with a transfer fee is
+    /// deposited with the fee on top, so every round's allocation arrives
+    /// whole.
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn create_launch<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateLaunch<'info>>,
+        params: LaunchParams,
+    ) -> Result<()> {
+        params.validate(Clock::get()?.unix_timestamp)?;
+        require!(
+            ctx.accounts.sale_mint.decimals <= MAX_SALE_DECIMALS,
+            ErrorCode::UnsupportedDecimals
+        );
+        let total_allocation = params.total_allocation()?;
+        
+        let deposit = with_transfer_fee(&ctx.accounts.sale_mint.to_account_info(), total_allocation)?;
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.authority_sale_account.to_account_info(),
+            mint: ctx.accounts.sale_mint.to_account_info(),
+            to: ctx.accounts.sale_vault.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.sale_token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, deposit, ctx.accounts.sale_mint.decimals)?;
+        ctx.accounts.sale_vault.reload()?;
+        require!(
+            ctx.accounts.sale_vault.amount >= total_allocation,
+            ErrorCode::AllocationShort
+        );
+        
+        let launch = &mut ctx.accounts.launch;
+        launch.base = ctx.accounts.base.key();
+        launch.authority = ctx.accounts.authority.key();
+        launch.sale_mint = ctx.accounts.sale_mint.key();
+        launch.sale_decimals = ctx.accounts.sale_mint.decimals;
+        launch.payment_mint = ctx.accounts.payment_mint.key();
+        launch.sale_vault = ctx.accounts.sale_vault.key();
+        launch.proceeds_vault = ctx.accounts.proceeds_vault.key();
+        launch.liquidity_vault = ctx.accounts.liquidity_vault.key();
+        launch.proceeds_receiver = ctx.accounts.proceeds_receiver.key();
+        launch.unsold_receiver = ctx.accounts.unsold_receiver.key();
+        launch.params = params;
+        launch.total_allocation = total_allocation;
+        launch.total_sold = 0;
+        launch.total_raised = 0;
+        launch.liquidity_amount = 0;
+        launch.state = LaunchState::Active;
+        launch.bumps.launch = *ctx.bumps.get("launch").unwrap();
+        launch.bumps.sale_vault = *ctx.bumps.get("sale_vault").unwrap();
+        launch.bumps.proceeds_vault = *ctx.bumps.get("proceeds_vault").unwrap();
+        launch.bumps.liquidity_vault = *ctx.bumps.get("liquidity_vault").unwrap();
+        
+        emit!(LaunchCreatedEvent {
+            launch: launch.key(),
+            sale_mint: launch.sale_mint,
+            payment_mint: launch.payment_mint,
+            total_allocation,
+            soft_cap: launch.params.soft_cap,
+            wallet_cap: launch.params.wallet_cap,
+        });
+        
+        Ok(())
+    }
+    
+    /// Buy `amount` sale tokens in the current round
+    ///
+    /// Fails if the price would exceed `max_payment`. `proof` is only read
+    /// when the launch has an allowlist.
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn buy<'info>(
+        ctx: Context<'_, '_, '_, 'info, Buy<'info>>,
+        amount: u64,
+        max_payment: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let buyer = ctx.accounts.buyer.key();
+        let launch = &mut ctx.accounts.launch;
+        
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(launch.state == LaunchState::Active, ErrorCode::LaunchNotActive);
+        if let Some(root) = launch.params.allowlist_root {
+            require!(
+                proof.len() <= distributor::MAX_PROOF_LEN
+                    && distributor::verify_proof(&proof, root, allowlist_leaf(&buyer)),
+                ErrorCode::NotAllowlisted
+            );
+        }
+        
+        let decimals = launch.sale_decimals;
+        let round_index = launch.params.current_round(now)?;
+        let round = &mut launch.params.rounds[round_index];
+        let round_sold = round.sold.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        require!(round_sold <= round.allocation, ErrorCode::RoundSoldOut);
+        let cost = round.curve.cost(round.sold, amount, decimals)?;
+        require!(cost > 0, ErrorCode::InvalidAmount);
+        require!(cost <= max_payment, ErrorCode::SlippageExceeded);
+        round.sold = round_sold;
+        
+        let contribution = &mut ctx.accounts.contribution;
+        contribution.init_if_new(launch.key(), buyer, *ctx.bumps.get("contribution").unwrap());
+        contribution.purchased = contribution.purchased.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        require!(contribution.purchased <= launch.params.wallet_cap, ErrorCode::WalletCapExceeded);
+        
+        let vault_before = ctx.accounts.proceeds_vault.amount;
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.buyer_payment_account.to_account_info(),
+            mint: ctx.accounts.payment_mint.to_account_info(),
+            to: ctx.accounts.proceeds_vault.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, cost, ctx.accounts.payment_mint.decimals)?;
+        // Proceeds and refunds are what the vault received, net of any
+        // payment-mint transfer fee
+        ctx.accounts.proceeds_vault.reload()?;
+        let received = ctx.accounts.proceeds_vault.amount - vault_before;
+        
+        contribution.paid = contribution.paid.checked_add(received)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        launch.total_sold = launch.total_sold.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        launch.total_raised = launch.total_raised.checked_add(received)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(PurchaseEvent {
+            launch: launch.key(),
+            buyer,
+            round: round_index as u8,
+            amount,
+            cost,
+            total_sold: launch.total_sold,
+            total_raised: launch.total_raised,
+        });
+        
+        Ok(())
+    }
+    
+    /// Settle the launch after its last round or once it sells out; anyone
+    /// may crank
+    ///
+    /// If the soft cap was met, `liquidity_bps` of the proceeds move into
+    /// the locked liquidity vault, the rest goes to the proceeds receiver
+    /// and unsold tokens return to the unsold receiver. Otherwise every
+    /// sale token is returned and buyers can refund.
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn finalize_launch<'info>(
+        ctx: Context<'_, '_, '_, 'info, FinalizeLaunch<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let launch = &ctx.accounts.launch;
+        
+        require!(launch.state == LaunchState::Active, ErrorCode::LaunchNotActive);
+        require!(
+            now >= launch.params.end_ts() || launch.total_sold == launch.total_allocation,
+            ErrorCode::LaunchStillRunning
+        );
+        
+        let succeeded = launch.total_raised >= launch.params.soft_cap;
+        let (liquidity, proceeds, unsold) = if succeeded {
+            let liquidity = u64::try_from(
+                u128::from(launch.total_raised) * u128::from(launch.params.liquidity_bps)
+                    / u128::from(LaunchParams::MAX_BPS),
+            ).map_err(|_| error!(ErrorCode::NumericOverflow))?;
+            (liquidity, launch.total_raised - liquidity, launch.total_allocation - launch.total_sold)
+        } else {
+            (0, 0, launch.total_allocation)
+        };
+        
+        let seeds = &[b"launch".as_ref(), launch.base.as_ref(), &[launch.bumps.launch]];
+        let signer = &[&seeds[..]];
+        let payment = (&ctx.accounts.payment_token_program, &ctx.accounts.payment_mint);
+        let sale = (&ctx.accounts.sale_token_program, &ctx.accounts.sale_mint);
+        let transfers = [
+            (payment, &ctx.accounts.proceeds_vault, ctx.accounts.liquidity_vault.to_account_info(), liquidity),
+            (payment, &ctx.accounts.proceeds_vault, ctx.accounts.proceeds_receiver.to_account_info(), proceeds),
+            (sale, &ctx.accounts.sale_vault, ctx.accounts.unsold_receiver.to_account_info(), unsold),
+        ];
+        for ((token_program, mint), from, to, amount) in transfers {
+            if amount == 0 {
+                continue;
+            }
+            let cpi_accounts = token_interface::TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to,
+                authority: launch.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer)
+                .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+            transfer_cpi::transfer_checked(cpi_ctx, amount, mint.decimals)?;
+        }
+        
+        let launch = &mut ctx.accounts.launch;
+        launch.state = if succeeded { LaunchState::Succeeded } else { LaunchState::Failed };
+        launch.liquidity_amount = liquidity;
+        
+        emit!(LaunchFinalizedEvent {
+            launch: launch.key(),
+            state: launch.state,
+            total_sold: launch.total_sold,
+            total_raised: launch.total_raised,
+            liquidity_locked: liquidity,
+            liquidity_unlock_at: launch.params.liquidity_unlock_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Deliver a buyer's tokens from a successful launch
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn claim_tokens<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimTokens<'info>>,
+    ) -> Result<()> {
+        let launch = &ctx.accounts.launch;
+        let contribution = &ctx.accounts.contribution;
+        
+        require!(launch.state == LaunchState::Succeeded, ErrorCode::LaunchNotSucceeded);
+        require!(!contribution.settled, ErrorCode::AlreadySettled);
+        
+        let seeds = &[b"launch".as_ref(), launch.base.as_ref(), &[launch.bumps.launch]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.sale_vault.to_account_info(),
+            mint: ctx.accounts.sale_mint.to_account_info(),
+            to: ctx.accounts.buyer_sale_account.to_account_info(),
+            authority: launch.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, contribution.purchased, ctx.accounts.sale_mint.decimals)?;
+        
+        let contribution = &mut ctx.accounts.contribution;
+        contribution.settled = true;
+        
+        emit!(ContributionSettledEvent {
+            launch: launch.key(),
+            buyer: contribution.buyer,
+            tokens: contribution.purchased,
+            refund: 0,
+        });
+        
+        Ok(())
+    }
+    
+    /// Return a buyer's payment from a launch that missed its soft cap
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn refund<'info>(
+        ctx: Context<'_, '_, '_, 'info, Refund<'info>>,
+    ) -> Result<()> {
+        let launch = &ctx.accounts.launch;
+        let contribution = &ctx.accounts.contribution;
+        
+        require!(launch.state == LaunchState::Failed, ErrorCode::LaunchNotFailed);
+        require!(!contribution.settled, ErrorCode::AlreadySettled);
+        
+        let seeds = &[b"launch".as_ref(), launch.base.as_ref(), &[launch.bumps.launch]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.proceeds_vault.to_account_info(),
+            mint: ctx.accounts.payment_mint.to_account_info(),
+            to: ctx.accounts.buyer_payment_account.to_account_info(),
+            authority: launch.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, contribution.paid, ctx.accounts.payment_mint.decimals)?;
+        
+        let contribution = &mut ctx.accounts.contribution;
+        contribution.settled = true;
+        
+        emit!(ContributionSettledEvent {
+            launch: launch.key(),
+            buyer: contribution.buyer,
+            tokens: 0,
+            refund: contribution.paid,
+        });
+        
+        Ok(())
+    }
+    
+    /// Release the locked liquidity share to the proceeds receiver once
+    /// `liquidity_unlock_at` has passed
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn withdraw_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawLiquidity<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let launch = &ctx.accounts.launch;
+        
+        require!(launch.state == LaunchState::Succeeded, ErrorCode::LaunchNotSucceeded);
+        require!(now >= launch.params.liquidity_unlock_at, ErrorCode::LiquidityLocked);
+        
+        let amount = ctx.accounts.liquidity_vault.amount;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        
+        let seeds = &[b"launch".as_ref(), launch.base.as_ref(), &[launch.bumps.launch]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.liquidity_vault.to_account_info(),
+            mint: ctx.accounts.payment_mint.to_account_info(),
+            to: ctx.accounts.proceeds_receiver.to_account_info(),
+            authority: launch.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.payment_mint.decimals)?;
+        
+        emit!(LiquidityWithdrawnEvent {
+            launch: launch.key(),
+            amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+}
+
+/// Allowlist leaf for `buyer`
+///
+/// Proofs are checked with `distributor::verify_proof`, but the leaf only
+/// commits to the buyer, unlike distributor claim leaves, so allowlist
+/// trees must be built from these leaves rather than `merkle_builder` output.
+pub fn allowlist_leaf(buyer: &Pubkey) -> [u8; 32] {
+    hashv(&[&[0u8], buyer.as_ref()]).to_bytes()
+}
+
+/// Largest sale mint precision `PriceCurve::cost` can price; `2 * 10^(2d)`
+/// must fit in u128
+pub const MAX_SALE_DECIMALS: u8 = 18;
+
+/// `amount` plus the transfer fee `mint` charges on it in the current
+/// epoch, so depositing the result delivers exactly `amount`
+fn with_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(amount);
+    }
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let fee = match mint.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config.calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(ErrorCode::NumericOverflow)?,
+        Err(_) => 0,
+    };
+    Ok(amount.checked_add(fee).ok_or(ErrorCode::NumericOverflow)?)
+}
+
+/// `a * b / c` rounded up, in u128
+fn mul_div_ceil(a: u128, b: u128, c: u128) -> Result<u128> {
+    let product = a.checked_mul(b).ok_or(ErrorCode::NumericOverflow)?;
+    Ok(product.div_ceil(c))
+}
+
+#[derive(Accounts)]
+pub struct CreateLaunch<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// Unique key the launch address is derived from
+    pub base: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = Launch::space(),
+        seeds = [b"launch", base.key().as_ref()],
+        bump
+    )]
+    pub launch: Box<Account<'info, Launch>>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"sale_vault", launch.key().as_ref()],
+        bump,
+        token::mint = sale_mint,
+        token::authority = launch,
+        token::token_program = sale_token_program,
+    )]
+    pub sale_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"proceeds_vault", launch.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = launch,
+        token::token_program = payment_token_program,
+    )]
+    pub proceeds_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"liquidity_vault", launch.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = launch,
+        token::token_program = payment_token_program,
+    )]
+    pub liquidity_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = authority_sale_account.owner == authority.key(),
+        constraint = authority_sale_account.mint == sale_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub authority_sale_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(constraint = proceeds_receiver.mint == payment_mint.key() @ ErrorCode::InvalidMint)]
+    pub proceeds_receiver: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(constraint = unsold_receiver.mint == sale_mint.key() @ ErrorCode::InvalidMint)]
+    pub unsold_receiver: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub sale_mint: Box<InterfaceAccount<'info, Mint>>,
+    pub payment_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    pub sale_token_program: Interface<'info, TokenInterface>,
+    pub payment_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Buy<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"launch", launch.base.as_ref()],
+        bump = launch.bumps.launch,
+    )]
+    pub launch: Box<Account<'info, Launch>>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = Contribution::space(),
+        seeds = [b"contribution", launch.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+    
+    #[account(mut, address = launch.proceeds_vault)]
+    pub proceeds_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = launch.payment_mint)]
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = buyer_payment_account.owner == buyer.key(),
+        constraint = buyer_payment_account.mint == launch.payment_mint @ ErrorCode::InvalidMint,
+    )]
+    pub buyer_payment_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeLaunch<'info> {
+    #[account(
+        mut,
+        seeds = [b"launch", launch.base.as_ref()],
+        bump = launch.bumps.launch,
+    )]
+    pub launch: Box<Account<'info, Launch>>,
+    
+    #[account(mut, address = launch.sale_vault)]
+    pub sale_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, address = launch.proceeds_vault)]
+    pub proceeds_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, address = launch.liquidity_vault)]
+    pub liquidity_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, address = launch.proceeds_receiver)]
+    pub proceeds_receiver: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, address = launch.unsold_receiver)]
+    pub unsold_receiver: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = launch.sale_mint)]
+    pub sale_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(address = launch.payment_mint)]
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+    
+    pub sale_token_program: Interface<'info, TokenInterface>,
+    pub payment_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTokens<'info> {
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"launch", launch.base.as_ref()],
+        bump = launch.bumps.launch,
+    )]
+    pub launch: Box<Account<'info, Launch>>,
+    
+    #[account(
+        mut,
+        seeds = [b"contribution", launch.key().as_ref(), buyer.key().as_ref()],
+        bump = contribution.bumps.contribution,
+    )]
+    pub contribution: Account<'info, Contribution>,
+    
+    #[account(mut, address = launch.sale_vault)]
+    pub sale_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = launch.sale_mint)]
+    pub sale_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = buyer_sale_account.mint == launch.sale_mint @ ErrorCode::InvalidMint,
+    )]
+    pub buyer_sale_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Refund<'info> {
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"launch", launch.base.as_ref()],
+        bump = launch.bumps.launch,
+    )]
+    pub launch: Box<Account<'info, Launch>>,
+    
+    #[account(
+        mut,
+        seeds = [b"contribution", launch.key().as_ref(), buyer.key().as_ref()],
+        bump = contribution.bumps.contribution,
+    )]
+    pub contribution: Account<'info, Contribution>,
+    
+    #[account(mut, address = launch.proceeds_vault)]
+    pub proceeds_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = launch.payment_mint)]
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = buyer_payment_account.mint == launch.payment_mint @ ErrorCode::InvalidMint,
+    )]
+    pub buyer_payment_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLiquidity<'info> {
+    #[account(
+        seeds = [b"launch", launch.base.as_ref()],
+        bump = launch.bumps.launch,
+    )]
+    pub launch: Box<Account<'info, Launch>>,
+    
+    #[account(mut, address = launch.liquidity_vault)]
+    pub liquidity_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, address = launch.proceeds_receiver)]
+    pub proceeds_receiver: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = launch.payment_mint)]
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Price of one whole sale token, in payment base units
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PriceCurve {
+    /// Every token costs `price`
+    Fixed { price: u64 },
+    /// Price starts at `start_price` and rises by `slope` for every whole
+    /// token sold in the round
+    Linear { start_price: u64, slope: u64 },
+}
+
+impl Default for PriceCurve {
+    fn default() -> Self {
+        PriceCurve::Fixed { price: 0 }
+    }
+}
+
+impl PriceCurve {
+    pub fn space() -> usize {
+        1 + 8 + 8 // Variant and largest payload
+    }
+    
+    /// Cost of buying `amount` base units after `sold` base units,
+    /// rounded up in the launch's favour
+    ///
+    /// The linear cost is the area under the curve,
+    /// `amount * (start + slope * (2 * sold + amount) / 2D) / D` with
+    /// `D = 10^decimals`. Whole tokens and the fractional remainder are
+    /// priced separately to stay within u128.
+    pub fn cost(&self, sold: u64, amount: u64, decimals: u8) -> Result<u64> {
+        require!(decimals <= MAX_SALE_DECIMALS, ErrorCode::UnsupportedDecimals);
+        let unit = 10u128.pow(u32::from(decimals));
+        let cost = match *self {
+            PriceCurve::Fixed { price } => mul_div_ceil(u128::from(price), u128::from(amount), unit)?,
+            PriceCurve::Linear { start_price, slope } => {
+                // Twice the average price over the purchase, scaled by D
+                let average_2d = (2 * unit)
+                    .checked_mul(u128::from(start_price))
+                    .and_then(|base| {
+                        u128::from(slope)
+                            .checked_mul(2 * u128::from(sold) + u128::from(amount))
+                            .and_then(|rise| base.checked_add(rise))
+                    })
+                    .ok_or(ErrorCode::NumericOverflow)?;
+                let whole = u128::from(amount) / unit;
+                let fraction = u128::from(amount) % unit;
+                mul_div_ceil(average_2d, whole, 2 * unit)?
+                    .checked_add(mul_div_ceil(average_2d, fraction, 2 * unit * unit)?)
+                    .ok_or(ErrorCode::NumericOverflow)?
+            }
+        };
+        u64::try_from(cost).map_err(|_| error!(ErrorCode::NumericOverflow))
+    }
+}
+
+/// A time-boxed round selling up to `allocation` base units
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct SaleRound {
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub curve: PriceCurve,
+    pub allocation: u64,
+    pub sold: u64,
+}
+
+impl SaleRound {
+    pub fn space() -> usize {
+        8 + // Start
+            8 + // End
+            PriceCurve::space() + // Curve
+            8 + // Allocation
+            8 // Sold
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct LaunchParams {
+    pub rounds: Vec<SaleRound>,       // Consecutive, non-overlapping rounds
+    pub wallet_cap: u64,              // Most sale tokens one wallet may buy
+    pub soft_cap: u64,                // Payment needed for the launch to succeed
+    pub allowlist_root: Option<[u8; 32]>, // Merkle root of allowed buyers
+    pub liquidity_bps: u16,           // Share of proceeds locked as liquidity
+    pub liquidity_unlock_at: i64,     // When locked liquidity can be withdrawn
+}
+
+impl LaunchParams {
+    pub const MAX_ROUNDS: usize = 4;
+    pub const MAX_BPS: u16 = 10_000;
+    
+    pub fn space() -> usize {
+        4 + Self::MAX_ROUNDS * SaleRound::space() + // Rounds (allocate max)
+            8 + // Wallet cap
+            8 + // Soft cap
+            1 + 32 + // Allowlist root
+            2 + // Liquidity bps
+            8 // Liquidity unlock at
+    }
+    
+    pub fn validate(&self, now: i64) -> Result<()> {
+        require!(
+            !self.rounds.is_empty() && self.rounds.len() <= Self::MAX_ROUNDS,
+            ErrorCode::InvalidLaunchParams
+        );
+        let mut previous_end = now;
+        for round in &self.rounds {
+            require!(
+                round.start_ts >= previous_end && round.start_ts < round.end_ts,
+                ErrorCode::InvalidLaunchParams
+            );
+            require!(round.allocation > 0 && round.sold == 0, ErrorCode::InvalidLaunchParams);
+            let priced = match round.curve {
+                PriceCurve::Fixed { price } => price > 0,
+                PriceCurve::Linear { start_price, slope } => start_price > 0 || slope > 0,
+            };
+            require!(priced, ErrorCode::InvalidLaunchParams);
+            previous_end = round.end_ts;
+        }
+        require!(self.wallet_cap > 0, ErrorCode::InvalidLaunchParams);
+        require!(self.liquidity_bps <= Self::MAX_BPS, ErrorCode::InvalidLaunchParams);
+        require!(self.liquidity_unlock_at >= previous_end, ErrorCode::InvalidLaunchParams);
+        Ok(())
+    }
+    
+    pub fn total_allocation(&self) -> Result<u64> {
+        self.rounds.iter()
+            .try_fold(0u64, |total, round| total.checked_add(round.allocation))
+            .ok_or_else(|| error!(ErrorCode::NumericOverflow))
+    }
+    
+    pub fn end_ts(&self) -> i64 {
+        self.rounds.last().map(|round| round.end_ts).unwrap_or_default()
+    }
+    
+    pub fn current_round(&self, now: i64) -> Result<usize> {
+        self.rounds.iter()
+            .position(|round| round.start_ts <= now && now < round.end_ts)
+            .ok_or_else(|| error!(ErrorCode::NoActiveRound))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LaunchState {
+    #[default]
+    Active,
+    Succeeded,
+    Failed,
+}
+
+
+#[account]
+#[derive(Default)]
+pub struct Launch {
+    pub base: Pubkey,                 // Key the launch address derives from
+    pub authority: Pubkey,            // Account that created the launch
+    pub sale_mint: Pubkey,            // Mint being sold
+    pub sale_decimals: u8,            // Decimals of the sale mint
+    pub payment_mint: Pubkey,         // Mint buyers pay in
+    pub sale_vault: Pubkey,           // PDA token account holding sale tokens
+    pub proceeds_vault: Pubkey,       // PDA token account holding payments
+    pub liquidity_vault: Pubkey,      // PDA token account holding locked liquidity
+    pub proceeds_receiver: Pubkey,    // Receives unlocked proceeds
+    pub unsold_receiver: Pubkey,      // Receives unsold sale tokens
+    pub params: LaunchParams,         // Rounds, caps and liquidity settings
+    pub total_allocation: u64,        // Sale tokens across all rounds
+    pub total_sold: u64,              // Sale tokens bought
+    pub total_raised: u64,            // Payment collected
+    pub liquidity_amount: u64,        // Payment locked at finalization
+    pub state: LaunchState,           // Lifecycle state
+    pub bumps: LaunchBumps,           // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct LaunchBumps {
+    pub launch: u8,
+    pub sale_vault: u8,
+    pub proceeds_vault: u8,
+    pub liquidity_vault: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct Contribution {
+    pub launch: Pubkey,               // Launch bought from
+    pub buyer: Pubkey,                // Buyer's public key
+    pub purchased: u64,               // Sale tokens bought
+    pub paid: u64,                    // Payment made
+    pub settled: bool,                // Tokens claimed or payment refunded
+    pub bumps: ContributionBumps,     // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ContributionBumps {
+    pub contribution: u8,
+}
+
+impl Launch {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Base
+            32 + // Authority
+            32 + // Sale mint
+            1 + // Sale decimals
+            32 + // Payment mint
+            32 + // Sale vault
+            32 + // Proceeds vault
+            32 + // Liquidity vault
+            32 + // Proceeds receiver
+            32 + // Unsold receiver
+            LaunchParams::space() + // Params
+            8 + // Total allocation
+            8 + // Total sold
+            8 + // Total raised
+            8 + // Liquidity amount
+            1 + // State
+            4 // Bumps
+    }
+}
+
+impl Contribution {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Launch
+            32 + // Buyer
+            8 + // Purchased
+            8 + // Paid
+            1 + // Settled
+            1 // Bump
+    }
+    
+    fn init_if_new(&mut self, launch: Pubkey, buyer: Pubkey, bump: u8) {
+        if self.buyer == Pubkey::default() {
+            self.launch = launch;
+            self.buyer = buyer;
+            self.bumps.contribution = bump;
+        }
+    }
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount must be positive")]
+    InvalidAmount,
+    #[msg("Token account mint does not match the launch")]
+    InvalidMint,
+    #[msg("Numeric overflow occurred")]
+    NumericOverflow,
+    #[msg("Rounds, caps or liquidity settings are invalid")]
+    InvalidLaunchParams,
+    #[msg("Launch is not active")]
+    LaunchNotActive,
+    #[msg("No sale round is open")]
+    NoActiveRound,
+    #[msg("Buyer is not on the allowlist")]
+    NotAllowlisted,
+    #[msg("Purchase exceeds the round's allocation")]
+    RoundSoldOut,
+    #[msg("Purchase exceeds the per-wallet cap")]
+    WalletCapExceeded,
+    #[msg("Price exceeds the maximum payment")]
+    SlippageExceeded,
+    #[msg("Launch is still running")]
+    LaunchStillRunning,
+    #[msg("Launch did not succeed")]
+    LaunchNotSucceeded,
+    #[msg("Launch did not fail")]
+    LaunchNotFailed,
+    #[msg("Contribution has already been settled")]
+    AlreadySettled,
+    #[msg("Liquidity is still locked")]
+    LiquidityLocked,
+    #[msg("Sale mint has more decimals than the price curve supports")]
+    UnsupportedDecimals,
+    #[msg("Sale vault received less than the total allocation")]
+    AllocationShort,
+}
+
+// Events
+#[event]
+pub struct LaunchCreatedEvent {
+    pub launch: Pubkey,
+    pub sale_mint: Pubkey,
+    pub payment_mint: Pubkey,
+    pub total_allocation: u64,
+    pub soft_cap: u64,
+    pub wallet_cap: u64,
+}
+
+#[event]
+pub struct PurchaseEvent {
+    pub launch: Pubkey,
+    pub buyer: Pubkey,
+    pub round: u8,
+    pub amount: u64,
+    pub cost: u64,
+    pub total_sold: u64,
+    pub total_raised: u64,
+}
+
+#[event]
+pub struct LaunchFinalizedEvent {
+    pub launch: Pubkey,
+    pub state: LaunchState,
+    pub total_sold: u64,
+    pub total_raised: u64,
+    pub liquidity_locked: u64,
+    pub liquidity_unlock_at: i64,
+}
+
+#[event]
+pub struct ContributionSettledEvent {
+    pub launch: Pubkey,
+    pub buyer: Pubkey,
+    pub tokens: u64,
+    pub refund: u64,
+}
+
+#[event]
+pub struct LiquidityWithdrawnEvent {
+    pub launch: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exact linear cost rounded up, `amount * (2D * start + slope * (2 * sold + amount)) / 2D^2`
+    fn exact_linear(start_price: u64, slope: u64, sold: u64, amount: u64, decimals: u8) -> u128 {
+        let unit = 10u128.pow(u32::from(decimals));
+        let rise = u128::from(slope) * (2 * u128::from(sold) + u128::from(amount));
+        let numerator = u128::from(amount) * (2 * unit * u128::from(start_price) + rise);
+        numerator.div_ceil(2 * unit * unit)
+    }
+
+    #[test]
+    fn fixed_cost_is_price_per_whole_token() {
+        let curve = PriceCurve::Fixed { price: 5 };
+        assert_eq!(curve.cost(0, 1_000_000, 6).unwrap(), 5);
+        assert_eq!(curve.cost(123, 3_000_000, 6).unwrap(), 15);
+        assert_eq!(curve.cost(0, 1_500_000, 6).unwrap(), 8);
+        // Dust still pays something
+        assert_eq!(curve.cost(0, 1, 6).unwrap(), 1);
+        assert_eq!(curve.cost(0, 0, 6).unwrap(), 0);
+    }
+
+    #[test]
+    fn linear_cost_is_the_area_under_the_curve() {
+        let curve = PriceCurve::Linear { start_price: 100, slope: 10 };
+        // 100 + 110 + 120, priced at the average of each token's range
+        assert_eq!(curve.cost(0, 3, 0).unwrap(), 345);
+        assert_eq!(curve.cost(3, 2, 0).unwrap(), 280);
+        assert_eq!(curve.cost(0, 5, 0).unwrap(), 625);
+        // 1.5 tokens averaging 107.5
+        assert_eq!(curve.cost(0, 1_500_000, 6).unwrap(), 162);
+        assert_eq!(curve.cost(0, 1_500_000, 6).unwrap() as u128, exact_linear(100, 10, 0, 1_500_000, 6));
+    }
+
+    #[test]
+    fn split_purchases_never_pay_less_than_the_exact_cost() {
+        let decimals = 6;
+        let curves = [
+            (PriceCurve::Fixed { price: 7 }, 7, 0),
+            (PriceCurve::Linear { start_price: 3, slope: 2 }, 3, 2),
+            (PriceCurve::Linear { start_price: 0, slope: 1_000 }, 0, 1_000),
+        ];
+        let total = 10_000_000 + 333_333;
+        for (curve, start_price, slope) in curves {
+            let exact = exact_linear(start_price, slope, 0, total, decimals);
+            let whole = u128::from(curve.cost(0, total, decimals).unwrap());
+            assert!(whole >= exact);
+            // Whole tokens and the remainder each round up once
+            assert!(whole <= exact + 2);
+            
+            for step in [7_919, 250_001, 1_000_000, 3_333_333] {
+                let mut sold = 0;
+                let mut paid = 0u128;
+                while sold < total {
+                    let amount = step.min(total - sold);
+                    let cost = curve.cost(sold, amount, decimals).unwrap();
+                    assert!(u128::from(cost) >= exact_linear(start_price, slope, sold, amount, decimals));
+                    paid += u128::from(cost);
+                    sold += amount;
+                }
+                assert!(paid >= exact, "step {step} paid {paid} < {exact}");
+            }
+        }
+    }
+
+    #[test]
+    fn max_decimals_prices_without_overflow_until_the_result_does() {
+        let one = 10u64.pow(u32::from(MAX_SALE_DECIMALS));
+        let fixed = PriceCurve::Fixed { price: 2 };
+        assert_eq!(fixed.cost(0, one, MAX_SALE_DECIMALS).unwrap(), 2);
+        assert_eq!(fixed.cost(0, 1, MAX_SALE_DECIMALS).unwrap(), 1);
+        
+        let linear = PriceCurve::Linear { start_price: 1_000, slope: 1 };
+        assert_eq!(linear.cost(0, 4 * one, MAX_SALE_DECIMALS).unwrap(), 4_008);
+        
+        // Intermediate products leave u128
+        let steep = PriceCurve::Linear { start_price: 0, slope: u64::MAX };
+        assert!(steep.cost(u64::MAX - 1, 1, MAX_SALE_DECIMALS).is_err());
+        // The cost itself leaves u64
+        let pricey = PriceCurve::Fixed { price: u64::MAX };
+        assert!(pricey.cost(0, u64::MAX, 0).is_err());
+        
+        assert!(fixed.cost(0, one, MAX_SALE_DECIMALS + 1).is_err());
+    }
+}
@@ -1,45 +1,80 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token};
-use std::mem::size_of;
+use anchor_lang::solana_program::{ed25519_program, sysvar::instructions as ix_sysvar};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
 
-declare_id!("MCPv1111111111111111111111111111111111111");
+declare_id!("MCPv111111111111111111111111111111111111111");
 
 #[program]
 pub mod mcp {
     use super::*;
 
     /// Initialize a new template account
-    pub fn initialize_template(
-        ctx: Context<InitializeTemplate>,
+    ///
+    /// `remaining_accounts` holds one writable tag index page per tag, in
+    /// the same order as `tags`.
+    pub fn initialize_template<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitializeTemplate<'info>>,
         template_id: String,
         template_name: String,
         template_version: String,
-        category: String,
         creator: Pubkey,
-        metadata: Vec<u8>,
+        metadata: ContentRef,
+        tags: Vec<String>,
     ) -> Result<()> {
         let template = &mut ctx.accounts.template;
+        let category = &mut ctx.accounts.category;
         
         // Validate inputs
         require!(template_id.len() <= 64, ErrorCode::TemplateTooLong);
         require!(template_name.len() <= 100, ErrorCode::NameTooLong);
         require!(template_version.len() <= 20, ErrorCode::VersionTooLong);
-        require!(category.len() <= 20, ErrorCode::CategoryTooLong);
-        require!(metadata.len() <= 1024, ErrorCode::MetadataTooLarge);
+        require!(category.is_active, ErrorCode::CategoryInactive);
+        metadata.validate()?;
+        TemplateAccount::validate_tags(&tags)?;
+        require!(
+            ctx.remaining_accounts.len() == tags.len(),
+            ErrorCode::IndexAccountsMismatch
+        );
+        
+        // Add the template to the discovery indexes
+        let template_key = template.key();
+        ctx.accounts.category_index.check(IndexKind::Category, &category.key().to_bytes())?;
+        ctx.accounts.category_index.push(template_key)?;
+        ctx.accounts.creator_index.check(IndexKind::Creator, &creator.to_bytes())?;
+        ctx.accounts.creator_index.push(template_key)?;
+        for (tag, page) in tags.iter().zip(ctx.remaining_accounts.iter()) {
+            TemplateIndex::update_page(page, IndexKind::Tag, &TemplateIndex::tag_key(tag), |index| {
+                index.push(template_key)
+            })?;
+        }
         
         // Initialize template data
         template.template_id = template_id;
         template.template_name = template_name;
         template.template_version = template_version;
-        template.category = category;
+        template.category = category.key();
+        template.tags = tags;
         template.creator = creator;
         template.metadata = metadata;
         template.created_at = Clock::get()?.unix_timestamp;
         template.updated_at = Clock::get()?.unix_timestamp;
         template.usage_count = 0;
         template.is_active = true;
+        template.pending_owner = None;
+        template.maintainers = Vec::new();
+        template.revenue_splits = Vec::new();
+        template.requires_license = false;
+        template.rating_count = 0;
+        template.rating_weight_total = 0;
+        template.rating_score_total = 0;
+        template.verified = false;
+        template.audit_policy = AuditPolicy::default();
+        template.update_delay = 0;
         template.bumps.template = *ctx.bumps.get("template").unwrap();
         
+        category.template_count = category.template_count.checked_add(1)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
         emit!(TemplateCreatedEvent {
             template_id: template.template_id.clone(),
             creator: template.creator,
@@ -50,8 +85,26 @@ pub mod mcp {
     }
     
     /// Record a template execution
-    pub fn record_execution(
-        ctx: Context<RecordExecution>,
+    ///
+    /// Templates with an audit policy need the current `template_version`
+    /// account plus `(attestation, auditor)` pairs in `remaining_accounts`.
+    ///
+    /// Per-execution listings can be paid from a credit account instead of
+    /// a license; the revenue recipients' token accounts then come first in
+    /// `remaining_accounts`, see `pay_template_revenue`. Accounts for the
+    /// mint's transfer hook go last, see `transfer_cpi::transfer_checked`.
+    ///
+    /// `executor` records the execution for `beneficiary` and `payer` funds
+    /// the account; all three may be the same wallet. The beneficiary
+    /// consents by being the executor, co-signing, having issued the
+    /// executor's `session`, or by signing
+    /// `ExecutionAccount::consent_message` in an ed25519 program
+    /// instruction earlier in the transaction.
+    ///
+    /// Credit spending counts against the executor's delegated limit, or
+    /// against the beneficiary's when the executor is their session key.
+    pub fn record_execution<'info>(
+        ctx: Context<'_, '_, '_, 'info, RecordExecution<'info>>,
         task_id: String,
         inputs_hash: String,
         outputs_hash: String,
@@ -65,10 +118,132 @@ pub mod mcp {
         require!(inputs_hash.len() <= 64, ErrorCode::HashTooLong);
         require!(outputs_hash.len() <= 64, ErrorCode::HashTooLong);
         
+        let beneficiary = ctx.accounts.beneficiary.key();
+        let executor = ctx.accounts.executor.key();
+        
+        // Without a license the execution is paid from a credit account at
+        // the listing's per-execution price
+        let charge = if template.requires_license && ctx.accounts.license.is_none() {
+            let credit = ctx.accounts.credit_account.as_ref()
+                .ok_or(ErrorCode::LicenseRequired)?;
+            let listing = ctx.accounts.listing.as_ref()
+                .ok_or(ErrorCode::LicenseRequired)?;
+            let price = match listing.pricing {
+                PricingModel::PerExecution { price } => price,
+                _ => return err!(ErrorCode::WrongPricingModel),
+            };
+            Some((credit.key(), price))
+        } else {
+            None
+        };
+        
+        if let Some(session) = ctx.accounts.session.as_ref() {
+            session.check(&template.key(), Clock::get()?.unix_timestamp)?;
+        } else if executor != beneficiary && !ctx.accounts.beneficiary.is_signer {
+            let instructions = ctx.accounts.instructions.as_ref()
+                .ok_or(ErrorCode::MissingUserConsent)?;
+            let message = ExecutionAccount::consent_message(
+                &beneficiary,
+                &executor,
+                &template.key(),
+                &task_id,
+                &inputs_hash,
+                charge.as_ref(),
+            );
+            verify_ed25519_consent(instructions, &beneficiary, &message)?;
+        }
+        
+        // Paid templates need a valid license held by the beneficiary, or
+        // credits when the listing charges per execution
+        let mut revenue_accounts = 0;
+        if template.requires_license {
+            if let Some(license) = ctx.accounts.license.as_mut() {
+                require!(license.template == template.key(), ErrorCode::InvalidLicense);
+                require!(license.holder == beneficiary, ErrorCode::InvalidLicense);
+                license.consume(Clock::get()?.unix_timestamp)?;
+            } else if let Some((_, price)) = charge {
+                let credit = ctx.accounts.credit_account.as_mut()
+                    .ok_or(ErrorCode::LicenseRequired)?;
+                let listing = ctx.accounts.listing.as_ref()
+                    .ok_or(ErrorCode::LicenseRequired)?;
+                let vault = ctx.accounts.credit_vault.as_ref()
+                    .ok_or(ErrorCode::InvalidCreditAccount)?;
+                let token_program = ctx.accounts.token_program.as_ref()
+                    .ok_or(ErrorCode::InvalidCreditAccount)?;
+                let mint = ctx.accounts.wale_mint.as_ref()
+                    .ok_or(ErrorCode::InvalidCreditAccount)?;
+                require!(vault.key() == credit.vault, ErrorCode::InvalidCreditAccount);
+                require!(mint.key() == credit.mint, ErrorCode::InvalidCreditAccount);
+                require!(listing.payment_mint == credit.mint, ErrorCode::InvalidPaymentMint);
+                require!(vault.amount >= price, ErrorCode::InsufficientCredit);
+                
+                // A session draws on its issuer's allowance, capped by the
+                // session's own limit; anyone else spends as themselves
+                let now = Clock::get()?.unix_timestamp;
+                let spender = match ctx.accounts.session.as_mut() {
+                    Some(session) => {
+                        session.spend(price)?;
+                        beneficiary
+                    }
+                    None => executor,
+                };
+                credit.spend(&spender, price, now)?;
+                
+                let shares = template.revenue_shares(price);
+                revenue_accounts = shares.len();
+                let seeds = &[
+                    b"credit".as_ref(),
+                    credit.owner.as_ref(),
+                    credit.mint.as_ref(),
+                    &[credit.bumps.credit],
+                ];
+                pay_template_revenue(
+                    &shares,
+                    vault,
+                    mint,
+                    &credit.to_account_info(),
+                    ctx.remaining_accounts,
+                    token_program,
+                    &[&seeds[..]],
+                )?;
+                
+                emit!(CreditSpentEvent {
+                    credit: credit.key(),
+                    spender,
+                    template: template.key(),
+                    amount: price,
+                    timestamp: now,
+                });
+            }
+        }
+        
+        // Audited templates need enough valid attestations on the current version
+        if template.audit_policy.required_attestations > 0 {
+            let version = ctx.accounts.template_version.as_ref()
+                .ok_or(ErrorCode::AuditRequirementNotMet)?;
+            version.check_current(template)?;
+            // Transfer-hook accounts may follow the attestation pairs
+            let accounts = ctx.remaining_accounts.get(revenue_accounts..)
+                .ok_or(ErrorCode::RevenueAccountsMismatch)?;
+            let pairs = accounts.iter().take_while(|account| *account.owner == crate::ID).count();
+            let valid = count_valid_attestations(
+                version,
+                &template.audit_policy,
+                &accounts[..pairs],
+                Clock::get()?.unix_timestamp,
+            )?;
+            require!(
+                valid >= usize::from(template.audit_policy.required_attestations),
+                ErrorCode::AuditRequirementNotMet
+            );
+        }
+        
         // Initialize execution data
         execution.task_id = task_id;
         execution.template = template.key();
-        execution.user = ctx.accounts.user.key();
+        execution.beneficiary_user = beneficiary;
+        execution.payer = ctx.accounts.payer.key();
+        execution.executor = executor;
         execution.inputs_hash = inputs_hash;
         execution.outputs_hash = outputs_hash;
         execution.status = status;
@@ -87,7 +262,8 @@ pub mod mcp {
         emit!(ExecutionRecordedEvent {
             task_id: execution.task_id.clone(),
             template_id: template.template_id.clone(),
-            user: execution.user,
+            user: execution.beneficiary_user,
+            executor: execution.executor,
             status: execution.status,
             timestamp: execution.started_at,
         });
@@ -96,6 +272,9 @@ pub mod mcp {
     }
     
     /// Update execution status
+    ///
+    /// Only the beneficiary or one of their live session keys may update;
+    /// recording an execution grants the executor nothing afterwards.
     pub fn update_execution(
         ctx: Context<UpdateExecution>,
         status: ExecutionStatus,
@@ -103,6 +282,10 @@ pub mod mcp {
     ) -> Result<()> {
         let execution = &mut ctx.accounts.execution;
         
+        if let Some(session) = ctx.accounts.session.as_ref() {
+            session.check(&execution.template, Clock::get()?.unix_timestamp)?;
+        }
+        
         // Validate the update
         require!(
             execution.status == ExecutionStatus::InProgress,
@@ -130,200 +313,4053 @@ pub mod mcp {
     }
     
     /// Update template metadata
-    pub fn update_template(
-        ctx: Context<UpdateTemplate>,
+    ///
+    /// When `tags` changes, `remaining_accounts` holds the index page that
+    /// currently lists the template for every dropped tag, followed by a
+    /// writable tail page for every added tag, each in tag order.
+    ///
+    /// Templates with an update delay must use `queue_template_update`.
+    pub fn update_template<'info>(
+        ctx: Context<'_, '_, '_, 'info, UpdateTemplate<'info>>,
         template_name: Option<String>,
         template_version: Option<String>,
-        metadata: Option<Vec<u8>>,
+        metadata: Option<ContentRef>,
         is_active: Option<bool>,
+        tags: Option<Vec<String>>,
+    ) -> Result<()> {
+        let template = &mut ctx.accounts.template;
+        
+        require!(template.update_delay == 0, ErrorCode::TimelockRequired);
+        
+        let update = TemplateUpdate {
+            template_name,
+            template_version,
+            metadata,
+            is_active,
+            tags,
+            ..TemplateUpdate::default()
+        };
+        update.validate()?;
+        apply_template_update(template, None, update, ctx.remaining_accounts)?;
+        
+        emit!(TemplateUpdatedEvent {
+            template_id: template.template_id.clone(),
+            updater: ctx.accounts.updater.key(),
+            timestamp: template.updated_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Raise the delay template updates must wait out
+    ///
+    /// Lowering the delay is itself a template update and has to be queued.
+    pub fn set_template_update_delay(
+        ctx: Context<ManageTemplate>,
+        delay: i64,
+    ) -> Result<()> {
+        let template = &mut ctx.accounts.template;
+        
+        require!(delay >= template.update_delay, ErrorCode::TimelockRequired);
+        require!(delay <= TemplateUpdate::MAX_DELAY, ErrorCode::InvalidTimelockDelay);
+        
+        template.update_delay = delay;
+        template.updated_at = Clock::get()?.unix_timestamp;
+        
+        emit!(TemplateUpdateDelayChangedEvent {
+            template: template.key(),
+            delay,
+            timestamp: template.updated_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Schedule a template update to apply after the template's delay
+    pub fn queue_template_update(
+        ctx: Context<QueueTemplateUpdate>,
+        update: TemplateUpdate,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let template = &ctx.accounts.template;
+        let pending = &mut ctx.accounts.pending_update;
+        
+        update.validate()?;
+        update.authorize(template, &ctx.accounts.updater.key())?;
+        
+        pending.template = template.key();
+        pending.update = update;
+        pending.eta = now.checked_add(template.update_delay)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        pending.queued_by = ctx.accounts.updater.key();
+        pending.queued_at = now;
+        pending.bumps.pending_update = *ctx.bumps.get("pending_update").unwrap();
+        
+        emit!(TemplateUpdateQueuedEvent {
+            template: pending.template,
+            queued_by: pending.queued_by,
+            update: pending.update.clone(),
+            eta: pending.eta,
+        });
+        
+        Ok(())
+    }
+    
+    /// Drop a queued template update; the protocol guardian or the
+    /// template owner may cancel
+    pub fn cancel_template_update(
+        ctx: Context<CancelTemplateUpdate>,
+    ) -> Result<()> {
+        emit!(TemplateUpdateCancelledEvent {
+            template: ctx.accounts.template.key(),
+            cancelled_by: ctx.accounts.canceller.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+    
+    /// Apply a queued template update once its ETA has passed; anyone may
+    /// crank. Tag index pages go in `remaining_accounts` as for
+    /// `update_template`, and updates touching the marketplace listing need
+    /// `listing`.
+    pub fn execute_template_update<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteTemplateUpdate<'info>>,
     ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let pending = &ctx.accounts.pending_update;
         let template = &mut ctx.accounts.template;
         
+        require!(now >= pending.eta, ErrorCode::TimelockNotElapsed);
+        // Ownership or maintainers may have changed since it was queued
+        pending.update.authorize(template, &pending.queued_by)?;
+        
+        apply_template_update(
+            template,
+            ctx.accounts.listing.as_mut(),
+            pending.update.clone(),
+            ctx.remaining_accounts,
+        )?;
+        
+        emit!(TemplateUpdateExecutedEvent {
+            template: template.key(),
+            queued_by: pending.queued_by,
+            timestamp: now,
+        });
+        emit!(TemplateUpdatedEvent {
+            template_id: template.template_id.clone(),
+            updater: pending.queued_by,
+            timestamp: template.updated_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Initialize the protocol configuration
+    ///
+    /// Only the program's upgrade authority may initialize, so the first
+    /// caller after deployment can't claim `admin`.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        admin: Pubkey,
+        curator: Pubkey,
+        guardian: Pubkey,
+        min_rating_stake: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        config.admin = admin;
+        config.curator = curator;
+        config.guardian = guardian;
+        config.wale_mint = ctx.accounts.wale_mint.key();
+        config.min_rating_stake = min_rating_stake;
+        config.category_count = 0;
+        config.bumps.config = *ctx.bumps.get("config").unwrap();
+        
+        emit!(ConfigUpdatedEvent {
+            admin: config.admin,
+            curator: config.curator,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+    
+    /// Update the protocol configuration
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        new_admin: Option<Pubkey>,
+        new_curator: Option<Pubkey>,
+        new_guardian: Option<Pubkey>,
+        min_rating_stake: Option<u64>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        if let Some(admin) = new_admin {
+            config.admin = admin;
+        }
+        
+        if let Some(curator) = new_curator {
+            config.curator = curator;
+        }
+        
+        if let Some(guardian) = new_guardian {
+            config.guardian = guardian;
+        }
+        
+        if let Some(stake) = min_rating_stake {
+            config.min_rating_stake = stake;
+        }
+        
+        emit!(ConfigUpdatedEvent {
+            admin: config.admin,
+            curator: config.curator,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+    
+    /// Register a new template category
+    ///
+    /// When `parent` itself has a parent, `remaining_accounts` holds the
+    /// parent's ancestors, nearest first, see `check_category_parent`.
+    pub fn create_category<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateCategory<'info>>,
+        slug: String,
+        display_name: String,
+    ) -> Result<()> {
+        let category = &mut ctx.accounts.category;
+        let config = &mut ctx.accounts.config;
+        
         // Validate inputs
-        if let Some(ref name) = template_name {
-            require!(name.len() <= 100, ErrorCode::NameTooLong);
-            template.template_name = name.clone();
+        Category::validate_slug(&slug)?;
+        require!(display_name.len() <= Category::MAX_DISPLAY_NAME_LEN, ErrorCode::NameTooLong);
+        if let Some(parent) = ctx.accounts.parent.as_ref() {
+            check_category_parent(&category.key(), parent, ctx.remaining_accounts)?;
         }
         
-        if let Some(ref version) = template_version {
-            require!(version.len() <= 20, ErrorCode::VersionTooLong);
-            template.template_version = version.clone();
+        // Initialize category data
+        category.slug = slug;
+        category.display_name = display_name;
+        category.parent = ctx.accounts.parent.as_ref().map(|parent| parent.key());
+        category.is_active = true;
+        category.template_count = 0;
+        category.created_at = Clock::get()?.unix_timestamp;
+        category.bumps.category = *ctx.bumps.get("category").unwrap();
+        
+        config.category_count = config.category_count.checked_add(1)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(CategoryCreatedEvent {
+            category: category.key(),
+            slug: category.slug.clone(),
+            parent: category.parent,
+            timestamp: category.created_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Update a category's display name, parent or active flag
+    ///
+    /// When `update_parent` is set the parent becomes the optional `parent`
+    /// account, so omitting it detaches the category. A new parent's
+    /// ancestors go in `remaining_accounts`, nearest first.
+    pub fn update_category<'info>(
+        ctx: Context<'_, '_, '_, 'info, UpdateCategory<'info>>,
+        display_name: Option<String>,
+        update_parent: bool,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        let category = &mut ctx.accounts.category;
+        
+        if let Some(name) = display_name {
+            require!(name.len() <= Category::MAX_DISPLAY_NAME_LEN, ErrorCode::NameTooLong);
+            category.display_name = name;
         }
         
-        if let Some(ref new_metadata) = metadata {
-            require!(new_metadata.len() <= 1024, ErrorCode::MetadataTooLarge);
-            template.metadata = new_metadata.clone();
+        if update_parent {
+            if let Some(parent) = ctx.accounts.parent.as_ref() {
+                check_category_parent(&category.key(), parent, ctx.remaining_accounts)?;
+            }
+            category.parent = ctx.accounts.parent.as_ref().map(|parent| parent.key());
         }
         
         if let Some(active) = is_active {
-            template.is_active = active;
+            category.is_active = active;
+        }
+        
+        emit!(CategoryUpdatedEvent {
+            category: category.key(),
+            slug: category.slug.clone(),
+            is_active: category.is_active,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+    
+    /// Open a discovery index page
+    ///
+    /// Page 0 can be opened at any time; later pages only once the previous
+    /// page (passed as `previous`) is full, so pages stay contiguous.
+    pub fn create_index_page(
+        ctx: Context<CreateIndexPage>,
+        kind: IndexKind,
+        key: [u8; 32],
+        page: u32,
+    ) -> Result<()> {
+        if page > 0 {
+            let previous = ctx.accounts.previous.as_ref()
+                .ok_or(ErrorCode::IndexPageOutOfOrder)?;
+            previous.check(kind, &key)?;
+            require!(previous.page + 1 == page, ErrorCode::IndexPageOutOfOrder);
+            require!(previous.is_full(), ErrorCode::IndexPageOutOfOrder);
         }
         
+        let index = &mut ctx.accounts.index;
+        index.kind = kind;
+        index.key = key;
+        index.page = page;
+        index.templates = Vec::new();
+        index.bumps.index = *ctx.bumps.get("index").unwrap();
+        
+        Ok(())
+    }
+    
+    /// Start (or cancel, with `None`) a two-step template ownership transfer
+    ///
+    /// Templates with an update delay must queue the nomination instead;
+    /// cancelling a pending transfer is always immediate.
+    pub fn transfer_template_ownership(
+        ctx: Context<TransferTemplateOwnership>,
+        new_owner: Option<Pubkey>,
+    ) -> Result<()> {
+        let template = &mut ctx.accounts.template;
+        
+        require!(
+            template.update_delay == 0 || new_owner.is_none(),
+            ErrorCode::TimelockRequired
+        );
+        template.pending_owner = new_owner;
+        
+        emit!(TemplateOwnershipTransferStartedEvent {
+            template_id: template.template_id.clone(),
+            owner: template.creator,
+            pending_owner: new_owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+    
+    /// Accept a pending template ownership transfer
+    ///
+    /// The template PDA stays keyed on the original registrant; only the
+    /// `creator` field and the creator index entries move.
+    pub fn accept_template_ownership(
+        ctx: Context<AcceptTemplateOwnership>,
+    ) -> Result<()> {
+        let template = &mut ctx.accounts.template;
+        let template_key = template.key();
+        let old_owner = template.creator;
+        let new_owner = ctx.accounts.new_owner.key();
+        
+        // Move the template between creator indexes
+        ctx.accounts.old_creator_index.check(IndexKind::Creator, &old_owner.to_bytes())?;
+        ctx.accounts.old_creator_index.remove(&template_key)?;
+        ctx.accounts.new_creator_index.check(IndexKind::Creator, &new_owner.to_bytes())?;
+        ctx.accounts.new_creator_index.push(template_key)?;
+        
+        template.creator = new_owner;
+        template.pending_owner = None;
         template.updated_at = Clock::get()?.unix_timestamp;
         
-        emit!(TemplateUpdatedEvent {
+        emit!(TemplateOwnershipTransferredEvent {
             template_id: template.template_id.clone(),
-            updater: ctx.accounts.creator.key(),
+            old_owner,
+            new_owner,
             timestamp: template.updated_at,
         });
         
         Ok(())
     }
-}
-
-#[derive(Accounts)]
-#[instruction(template_id: String)]
-pub struct InitializeTemplate<'info> {
-    #[account(mut)]
-    pub creator: Signer<'info>,
-    
-    #[account(
-        init,
-        payer = creator,
-        space = TemplateAccount::space(&template_id),
-        seeds = [
-            b"template",
-            template_id.as_bytes(),
-            creator.key().as_ref(),
-        ],
-        bump
-    )]
-    pub template: Account<'info, TemplateAccount>,
     
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-#[instruction(task_id: String)]
-pub struct RecordExecution<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+    /// Replace the list of maintainers allowed to update the template
+    ///
+    /// Templates with an update delay must queue the change instead.
+    pub fn set_template_maintainers(
+        ctx: Context<ManageTemplate>,
+        maintainers: Vec<Pubkey>,
+    ) -> Result<()> {
+        let template = &mut ctx.accounts.template;
+        
+        require!(template.update_delay == 0, ErrorCode::TimelockRequired);
+        TemplateAccount::validate_maintainers(&maintainers)?;
+        
+        template.maintainers = maintainers;
+        template.updated_at = Clock::get()?.unix_timestamp;
+        
+        emit!(TemplateMaintainersUpdatedEvent {
+            template_id: template.template_id.clone(),
+            maintainers: template.maintainers.clone(),
+            timestamp: template.updated_at,
+        });
+        
+        Ok(())
+    }
     
-    #[account(mut)]
-    pub template: Account<'info, TemplateAccount>,
+    /// Replace the revenue-split table
+    ///
+    /// Shares are in basis points and must add up to 10,000. An empty table
+    /// sends all template revenue to the owner. Templates with an update
+    /// delay must queue the change instead.
+    pub fn set_revenue_splits(
+        ctx: Context<ManageTemplate>,
+        splits: Vec<RevenueShare>,
+    ) -> Result<()> {
+        let template = &mut ctx.accounts.template;
+        
+        require!(template.update_delay == 0, ErrorCode::TimelockRequired);
+        RevenueShare::validate(&splits)?;
+        
+        template.revenue_splits = splits;
+        template.updated_at = Clock::get()?.unix_timestamp;
+        
+        emit!(RevenueSplitsUpdatedEvent {
+            template_id: template.template_id.clone(),
+            splits: template.revenue_splits.clone(),
+            timestamp: template.updated_at,
+        });
+        
+        Ok(())
+    }
     
-    #[account(
-        init,
-        payer = user,
-        space = ExecutionAccount::space(&task_id),
-        seeds = [
-            b"execution",
-            task_id.as_bytes(),
-            user.key().as_ref(),
-        ],
-        bump
-    )]
-    pub execution: Account<'info, ExecutionAccount>,
+    /// List a template on the marketplace
+    ///
+    /// On templates with an update delay a listing starts out free; paid
+    /// pricing gates executions and must be queued as a template update.
+    pub fn create_listing(
+        ctx: Context<CreateListing>,
+        pricing: PricingModel,
+    ) -> Result<()> {
+        let listing = &mut ctx.accounts.listing;
+        let template = &mut ctx.accounts.template;
+        
+        require!(
+            template.update_delay == 0 || pricing == PricingModel::Free,
+            ErrorCode::TimelockRequired
+        );
+        pricing.validate()?;
+        
+        listing.template = template.key();
+        listing.payment_mint = ctx.accounts.payment_mint.key();
+        listing.pricing = pricing;
+        listing.is_active = true;
+        listing.sales_count = 0;
+        listing.revenue = 0;
+        listing.created_at = Clock::get()?.unix_timestamp;
+        listing.bumps.listing = *ctx.bumps.get("listing").unwrap();
+        
+        template.requires_license = listing.requires_license();
+        
+        emit!(ListingUpdatedEvent {
+            listing: listing.key(),
+            template: listing.template,
+            pricing: listing.pricing.clone(),
+            is_active: listing.is_active,
+            timestamp: listing.created_at,
+        });
+        
+        Ok(())
+    }
     
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct UpdateExecution<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+    /// Change a listing's pricing or pause it
+    ///
+    /// Existing licenses stay valid under the terms they were bought with.
+    /// On templates with an update delay only pausing is immediate; other
+    /// changes must be queued as a template update.
+    pub fn update_listing(
+        ctx: Context<UpdateListing>,
+        pricing: Option<PricingModel>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        let listing = &mut ctx.accounts.listing;
+        let template = &mut ctx.accounts.template;
+        
+        require!(
+            template.update_delay == 0 || (pricing.is_none() && is_active != Some(true)),
+            ErrorCode::TimelockRequired
+        );
+        
+        if let Some(new_pricing) = pricing {
+            new_pricing.validate()?;
+            listing.pricing = new_pricing;
+        }
+        
+        if let Some(active) = is_active {
+            listing.is_active = active;
+        }
+        
+        template.requires_license = listing.requires_license();
+        
+        emit!(ListingUpdatedEvent {
+            listing: listing.key(),
+            template: listing.template,
+            pricing: listing.pricing.clone(),
+            is_active: listing.is_active,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
     
-    #[account(
-        mut,
-        constraint = execution.user == user.key(),
-    )]
-    pub execution: Account<'info, ExecutionAccount>,
-}
-
-#[derive(Accounts)]
-pub struct UpdateTemplate<'info> {
-    #[account(mut)]
-    pub creator: Signer<'info>,
+    /// Buy a one-time license or a bundle of per-execution uses
+    ///
+    /// `quantity` is the number of executions for per-execution pricing and
+    /// is ignored for one-time licenses. `remaining_accounts` holds the
+    /// revenue recipients' token accounts, see `pay_template_revenue`,
+    /// followed by any transfer-hook accounts for the payment mint.
+    pub fn purchase_license<'info>(
+        ctx: Context<'_, '_, '_, 'info, PurchaseLicense<'info>>,
+        quantity: u32,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let (amount, executions) = match ctx.accounts.listing.pricing {
+            PricingModel::OneTimeLicense { price } => {
+                require!(!ctx.accounts.license.perpetual, ErrorCode::LicenseAlreadyOwned);
+                (price, 0)
+            }
+            PricingModel::PerExecution { price } => {
+                require!(quantity > 0, ErrorCode::InvalidQuantity);
+                let amount = price.checked_mul(u64::from(quantity))
+                    .ok_or(ErrorCode::NumericOverflow)?;
+                (amount, quantity)
+            }
+            _ => return err!(ErrorCode::WrongPricingModel),
+        };
+        
+        collect_license_payment(&ctx, amount)?;
+        
+        let license = &mut ctx.accounts.license;
+        license.init_if_new(&ctx.accounts.listing, ctx.accounts.buyer.key(), *ctx.bumps.get("license").unwrap());
+        match ctx.accounts.listing.pricing {
+            PricingModel::OneTimeLicense { .. } => license.perpetual = true,
+            _ => {
+                license.executions_remaining = license.executions_remaining
+                    .checked_add(u64::from(executions))
+                    .ok_or(ErrorCode::NumericOverflow)?;
+            }
+        }
+        
+        record_license_sale(&mut ctx.accounts.listing, license, amount, now)
+    }
     
-    #[account(
+    /// Start or extend a time-based subscription by `periods` periods
+    pub fn subscribe<'info>(
+        ctx: Context<'_, '_, '_, 'info, PurchaseLicense<'info>>,
+        periods: u32,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let (price, period) = match ctx.accounts.listing.pricing {
+            PricingModel::Subscription { price, period } => (price, period),
+            _ => return err!(ErrorCode::WrongPricingModel),
+        };
+        require!(periods > 0, ErrorCode::InvalidQuantity);
+        
+        let amount = price.checked_mul(u64::from(periods))
+            .ok_or(ErrorCode::NumericOverflow)?;
+        let extension = period.checked_mul(i64::from(periods))
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        collect_license_payment(&ctx, amount)?;
+        
+        let license = &mut ctx.accounts.license;
+        license.init_if_new(&ctx.accounts.listing, ctx.accounts.buyer.key(), *ctx.bumps.get("license").unwrap());
+        // Renewals stack on top of time already paid for
+        let start = license.expires_at.max(now);
+        license.expires_at = start.checked_add(extension)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        record_license_sale(&mut ctx.accounts.listing, license, amount, now)
+    }
+    
+    /// Rate a template after a successful execution of it
+    ///
+    /// Each rater has one rating per template; rating again replaces the
+    /// previous score. Weight is the square root of the rater's locked
+    /// rating stake in whole tokens, so buying influence gets expensive
+    /// fast. Rating relocks the stake for `RatingStake::LOCK_PERIOD`, so the
+    /// same tokens can't be moved to another wallet to rate again.
+    pub fn rate_template(
+        ctx: Context<RateTemplate>,
+        score: u8,
+        review_hash: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let template = &mut ctx.accounts.template;
+        let rating = &mut ctx.accounts.rating;
+        
+        require!(
+            (Rating::MIN_SCORE..=Rating::MAX_SCORE).contains(&score),
+            ErrorCode::InvalidRatingScore
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        let rater_stake = &mut ctx.accounts.rater_stake;
+        let stake = rater_stake.amount;
+        require!(
+            stake > 0 && stake >= config.min_rating_stake,
+            ErrorCode::InsufficientRatingStake
+        );
+        let unit = 10u64.checked_pow(u32::from(ctx.accounts.wale_mint.decimals))
+            .ok_or(ErrorCode::NumericOverflow)?;
+        let weight = integer_sqrt(stake / unit).max(1);
+        rater_stake.locked_until = rater_stake.locked_until
+            .max(now.checked_add(RatingStake::LOCK_PERIOD).ok_or(ErrorCode::NumericOverflow)?);
+        
+        // Replace the rater's previous contribution, if any
+        if rating.weight > 0 {
+            template.rating_weight_total -= rating.weight;
+            template.rating_score_total -= u64::from(rating.score) * rating.weight;
+        } else {
+            template.rating_count = template.rating_count.checked_add(1)
+                .ok_or(ErrorCode::NumericOverflow)?;
+            rating.template = template.key();
+            rating.rater = ctx.accounts.rater.key();
+            rating.bumps.rating = *ctx.bumps.get("rating").unwrap();
+        }
+        
+        rating.score = score;
+        rating.weight = weight;
+        rating.review_hash = review_hash;
+        rating.execution = ctx.accounts.execution.key();
+        rating.rated_at = now;
+        
+        template.rating_weight_total = template.rating_weight_total.checked_add(weight)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        template.rating_score_total = template.rating_score_total
+            .checked_add(u64::from(score) * weight)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(TemplateRatedEvent {
+            template: template.key(),
+            rater: rating.rater,
+            score,
+            weight,
+            review_hash,
+            timestamp: rating.rated_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Lock WALE in the caller's rating stake, which weighs their ratings
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn lock_rating_stake<'info>(
+        ctx: Context<'_, '_, '_, 'info, LockRatingStake<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidQuantity);
+        
+        let vault_before = ctx.accounts.vault.amount;
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.owner_token_account.to_account_info(),
+            mint: ctx.accounts.wale_mint.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.wale_mint.decimals)?;
+        let amount = received(&mut ctx.accounts.vault, vault_before)?;
+        
+        let stake = &mut ctx.accounts.rating_stake;
+        if stake.owner == Pubkey::default() {
+            stake.owner = ctx.accounts.owner.key();
+            stake.vault = ctx.accounts.vault.key();
+            stake.bumps.rating_stake = *ctx.bumps.get("rating_stake").unwrap();
+            stake.bumps.vault = *ctx.bumps.get("vault").unwrap();
+        }
+        stake.amount = stake.amount.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(RatingStakeChangedEvent {
+            owner: stake.owner,
+            amount: stake.amount,
+            locked_until: stake.locked_until,
+        });
+        
+        Ok(())
+    }
+    
+    /// Withdraw rating stake once the lock from the owner's last rating
+    /// has expired
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn unlock_rating_stake<'info>(
+        ctx: Context<'_, '_, '_, 'info, UnlockRatingStake<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let stake = &ctx.accounts.rating_stake;
+        require!(amount > 0 && amount <= stake.amount, ErrorCode::InvalidQuantity);
+        require!(
+            Clock::get()?.unix_timestamp >= stake.locked_until,
+            ErrorCode::RatingStakeLocked
+        );
+        
+        let seeds = &[b"rating_stake".as_ref(), stake.owner.as_ref(), &[stake.bumps.rating_stake]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.vault.to_account_info(),
+            mint: ctx.accounts.wale_mint.to_account_info(),
+            to: ctx.accounts.owner_token_account.to_account_info(),
+            authority: stake.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.wale_mint.decimals)?;
+        
+        let stake = &mut ctx.accounts.rating_stake;
+        stake.amount -= amount;
+        
+        emit!(RatingStakeChangedEvent {
+            owner: stake.owner,
+            amount: stake.amount,
+            locked_until: stake.locked_until,
+        });
+        
+        Ok(())
+    }
+    
+    /// Grant or revoke a template's verified badge
+    pub fn set_template_verified(
+        ctx: Context<SetTemplateVerified>,
+        verified: bool,
+    ) -> Result<()> {
+        let template = &mut ctx.accounts.template;
+        
+        template.verified = verified;
+        
+        emit!(TemplateVerificationChangedEvent {
+            template: template.key(),
+            template_id: template.template_id.clone(),
+            curator: ctx.accounts.curator.key(),
+            verified,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+    
+    /// Register an auditor allowed to attest template versions
+    pub fn register_auditor(
+        ctx: Context<RegisterAuditor>,
+        name: String,
+    ) -> Result<()> {
+        let auditor = &mut ctx.accounts.auditor;
+        
+        require!(name.len() <= Auditor::MAX_NAME_LEN, ErrorCode::NameTooLong);
+        
+        auditor.authority = ctx.accounts.auditor_authority.key();
+        auditor.name = name;
+        auditor.is_active = true;
+        auditor.attestation_count = 0;
+        auditor.registered_at = Clock::get()?.unix_timestamp;
+        auditor.bumps.auditor = *ctx.bumps.get("auditor").unwrap();
+        
+        emit!(AuditorUpdatedEvent {
+            auditor: auditor.authority,
+            name: auditor.name.clone(),
+            is_active: auditor.is_active,
+            timestamp: auditor.registered_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Suspend or reinstate an auditor; attestations from inactive auditors
+    /// stop counting towards audit policies
+    pub fn set_auditor_active(
+        ctx: Context<SetAuditorActive>,
+        is_active: bool,
+    ) -> Result<()> {
+        let auditor = &mut ctx.accounts.auditor;
+        
+        auditor.is_active = is_active;
+        
+        emit!(AuditorUpdatedEvent {
+            auditor: auditor.authority,
+            name: auditor.name.clone(),
+            is_active,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+    
+    /// Snapshot the template's current version string and content digest
+    ///
+    /// The version account is keyed on both, so content changed without a
+    /// version bump gets a snapshot of its own instead of colliding with
+    /// the one already published for that version string.
+    pub fn publish_template_version(
+        ctx: Context<PublishTemplateVersion>,
+    ) -> Result<()> {
+        let template = &ctx.accounts.template;
+        let version = &mut ctx.accounts.template_version;
+        
+        version.template = template.key();
+        version.version = template.template_version.clone();
+        version.content_digest = template.metadata.digest();
+        version.attestation_count = 0;
+        version.published_at = Clock::get()?.unix_timestamp;
+        version.bumps.template_version = *ctx.bumps.get("template_version").unwrap();
+        
+        emit!(TemplateVersionPublishedEvent {
+            template: version.template,
+            template_version: version.key(),
+            version: version.version.clone(),
+            content_digest: version.content_digest,
+            timestamp: version.published_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Record an auditor's review of a specific template version
+    pub fn attest_template_version(
+        ctx: Context<AttestTemplateVersion>,
+        report_hash: [u8; 32],
+        severity: SeveritySummary,
+        expires_at: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let attestation = &mut ctx.accounts.attestation;
+        let auditor = &mut ctx.accounts.auditor;
+        let version = &mut ctx.accounts.template_version;
+        
+        require!(expires_at > now, ErrorCode::InvalidAttestationExpiry);
+        
+        attestation.auditor = auditor.authority;
+        attestation.template_version = version.key();
+        attestation.report_hash = report_hash;
+        attestation.severity = severity;
+        attestation.expires_at = expires_at;
+        attestation.attested_at = now;
+        attestation.bumps.attestation = *ctx.bumps.get("attestation").unwrap();
+        
+        auditor.attestation_count = auditor.attestation_count.checked_add(1)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        version.attestation_count = version.attestation_count.checked_add(1)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(TemplateVersionAttestedEvent {
+            template: version.template,
+            template_version: version.key(),
+            auditor: attestation.auditor,
+            report_hash,
+            severity: attestation.severity.clone(),
+            expires_at,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+    
+    /// Set how many valid attestations executions of the template require
+    ///
+    /// Tightening applies at once. On templates with an update delay,
+    /// loosening is a template update and has to be queued, so clients
+    /// relying on the audit requirement get the delay to react.
+    pub fn set_audit_policy(
+        ctx: Context<ManageTemplate>,
+        policy: AuditPolicy,
+    ) -> Result<()> {
+        let template = &mut ctx.accounts.template;
+        
+        policy.validate()?;
+        require!(
+            template.update_delay == 0 || policy.is_at_least(&template.audit_policy),
+            ErrorCode::TimelockRequired
+        );
+        
+        template.audit_policy = policy;
+        template.updated_at = Clock::get()?.unix_timestamp;
+        
+        emit!(AuditPolicyUpdatedEvent {
+            template: template.key(),
+            policy: template.audit_policy.clone(),
+            timestamp: template.updated_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Open a WALE bug bounty for a template
+    ///
+    /// Reports are resolved by the arbiters only; the creator can't be one,
+    /// so they can't approve payouts to a wallet of their own.
+    pub fn create_bounty(
+        ctx: Context<CreateBounty>,
+        expires_at: i64,
+        arbiters: Vec<Pubkey>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let bounty = &mut ctx.accounts.bounty;
+        
+        require!(expires_at > now, ErrorCode::InvalidBountyExpiry);
+        require!(arbiters.len() <= Bounty::MAX_ARBITERS, ErrorCode::TooManyArbiters);
+        require!(
+            !arbiters.is_empty() && !arbiters.contains(&ctx.accounts.creator.key()),
+            ErrorCode::BountyArbiterRequired
+        );
+        
+        bounty.template = ctx.accounts.template.key();
+        bounty.vault = ctx.accounts.vault.key();
+        bounty.arbiters = arbiters;
+        bounty.funded_total = 0;
+        bounty.paid_total = 0;
+        bounty.refund_pool = 0;
+        bounty.refunds_started = false;
+        bounty.report_count = 0;
+        bounty.expires_at = expires_at;
+        bounty.created_at = now;
+        bounty.bumps.bounty = *ctx.bumps.get("bounty").unwrap();
+        bounty.bumps.vault = *ctx.bumps.get("vault").unwrap();
+        
+        emit!(BountyCreatedEvent {
+            bounty: bounty.key(),
+            template: bounty.template,
+            arbiters: bounty.arbiters.clone(),
+            expires_at,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+    
+    /// Add WALE from the template creator's wallet to a bounty vault
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn fund_bounty<'info>(
+        ctx: Context<'_, '_, '_, 'info, FundBounty<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(amount > 0, ErrorCode::InvalidQuantity);
+        require!(now < ctx.accounts.bounty.expires_at, ErrorCode::BountyExpired);
+        
+        let vault_before = ctx.accounts.vault.amount;
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            mint: ctx.accounts.wale_mint.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.wale_mint.decimals)?;
+        let amount = received(&mut ctx.accounts.vault, vault_before)?;
+        
+        record_bounty_deposit(
+            &mut ctx.accounts.bounty,
+            &mut ctx.accounts.deposit,
+            ctx.accounts.funder.key(),
+            *ctx.bumps.get("deposit").unwrap(),
+            amount,
+            now,
+        )
+    }
+    
+    /// Create the protocol treasury vault that can fund bounties
+    pub fn initialize_treasury(
+        _ctx: Context<InitializeTreasury>,
+    ) -> Result<()> {
+        Ok(())
+    }
+    
+    /// Add WALE from the protocol treasury to a bounty vault
+    ///
+    /// The deposit is recorded against the treasury PDA, so expiry refunds
+    /// go back to the treasury rather than to the admin's wallet.
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn fund_bounty_from_treasury<'info>(
+        ctx: Context<'_, '_, '_, 'info, FundBountyFromTreasury<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(amount > 0, ErrorCode::InvalidQuantity);
+        require!(now < ctx.accounts.bounty.expires_at, ErrorCode::BountyExpired);
+        
+        let vault_before = ctx.accounts.vault.amount;
+        let seeds = &[b"treasury".as_ref(), &[*ctx.bumps.get("treasury").unwrap()]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.treasury_vault.to_account_info(),
+            mint: ctx.accounts.wale_mint.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.treasury.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.wale_mint.decimals)?;
+        let amount = received(&mut ctx.accounts.vault, vault_before)?;
+        
+        record_bounty_deposit(
+            &mut ctx.accounts.bounty,
+            &mut ctx.accounts.deposit,
+            ctx.accounts.treasury.key(),
+            *ctx.bumps.get("deposit").unwrap(),
+            amount,
+            now,
+        )
+    }
+    
+    /// Submit a report as a hash commitment
+    ///
+    /// `commitment` is the SHA-256 of the report and a secret salt; the
+    /// researcher reveals both to the creator or arbiters off-chain.
+    pub fn submit_bounty_report(
+        ctx: Context<SubmitBountyReport>,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let bounty = &mut ctx.accounts.bounty;
+        let report = &mut ctx.accounts.report;
+        
+        require!(now < bounty.expires_at, ErrorCode::BountyExpired);
+        
+        report.bounty = bounty.key();
+        report.researcher = ctx.accounts.researcher.key();
+        report.commitment = commitment;
+        report.status = ReportStatus::Pending;
+        report.payout = 0;
+        report.submitted_at = now;
+        report.resolved_at = 0;
+        report.bumps.report = *ctx.bumps.get("report").unwrap();
+        
+        bounty.report_count = bounty.report_count.checked_add(1)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(BountyReportSubmittedEvent {
+            bounty: bounty.key(),
+            report: report.key(),
+            researcher: report.researcher,
+            commitment,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+    
+    /// Approve a report and pay the researcher straight from the vault
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn approve_bounty_payout<'info>(
+        ctx: Context<'_, '_, '_, 'info, ResolveBountyReport<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(amount > 0, ErrorCode::InvalidQuantity);
+        require!(amount <= ctx.accounts.vault.amount, ErrorCode::InsufficientBountyFunds);
+        require!(now < ctx.accounts.bounty.expires_at, ErrorCode::BountyExpired);
+        
+        let researcher_token_account = ctx.accounts.researcher_token_account.as_ref()
+            .ok_or(ErrorCode::InvalidBountyPayoutAccount)?;
+        require!(
+            researcher_token_account.owner == ctx.accounts.report.researcher,
+            ErrorCode::InvalidBountyPayoutAccount
+        );
+        
+        let template = ctx.accounts.bounty.template;
+        let seeds = &[b"bounty".as_ref(), template.as_ref(), &[ctx.accounts.bounty.bumps.bounty]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.vault.to_account_info(),
+            mint: ctx.accounts.wale_mint.to_account_info(),
+            to: researcher_token_account.to_account_info(),
+            authority: ctx.accounts.bounty.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.wale_mint.decimals)?;
+        
+        let bounty = &mut ctx.accounts.bounty;
+        let report = &mut ctx.accounts.report;
+        report.status = ReportStatus::Approved;
+        report.payout = amount;
+        report.resolved_at = now;
+        bounty.paid_total = bounty.paid_total.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(BountyReportResolvedEvent {
+            bounty: bounty.key(),
+            report: report.key(),
+            researcher: report.researcher,
+            resolver: ctx.accounts.resolver.key(),
+            status: report.status,
+            payout: amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+    
+    /// Reject a report without payout
+    pub fn reject_bounty_report(
+        ctx: Context<ResolveBountyReport>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let report = &mut ctx.accounts.report;
+        
+        report.status = ReportStatus::Rejected;
+        report.resolved_at = now;
+        
+        emit!(BountyReportResolvedEvent {
+            bounty: ctx.accounts.bounty.key(),
+            report: report.key(),
+            researcher: report.researcher,
+            resolver: ctx.accounts.resolver.key(),
+            status: report.status,
+            payout: 0,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+    
+    /// Return a funder's pro-rata share of unclaimed funds after expiry;
+    /// anyone may crank, funds only go to the funder's own token account
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn refund_bounty<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefundBounty<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= ctx.accounts.bounty.expires_at, ErrorCode::BountyNotExpired);
+        require!(!ctx.accounts.deposit.refunded, ErrorCode::BountyAlreadyRefunded);
+        
+        // Freeze what's left on the first refund so every funder gets the
+        // same pro-rata share regardless of refund order
+        if !ctx.accounts.bounty.refunds_started {
+            ctx.accounts.bounty.refund_pool = ctx.accounts.vault.amount;
+            ctx.accounts.bounty.refunds_started = true;
+        }
+        let bounty = &ctx.accounts.bounty;
+        let amount = (u128::from(bounty.refund_pool) * u128::from(ctx.accounts.deposit.amount)
+            / u128::from(bounty.funded_total)) as u64;
+        
+        if amount > 0 {
+            let seeds = &[b"bounty".as_ref(), bounty.template.as_ref(), &[bounty.bumps.bounty]];
+            let signer = &[&seeds[..]];
+            let cpi_accounts = token_interface::TransferChecked {
+                from: ctx.accounts.vault.to_account_info(),
+                mint: ctx.accounts.wale_mint.to_account_info(),
+                to: ctx.accounts.funder_token_account.to_account_info(),
+                authority: bounty.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            )
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+            transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.wale_mint.decimals)?;
+        }
+        
+        ctx.accounts.deposit.refunded = true;
+        
+        emit!(BountyRefundedEvent {
+            bounty: ctx.accounts.bounty.key(),
+            funder: ctx.accounts.deposit.funder,
+            amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+    
+    /// Open a prepaid WALE credit account for the signer
+    pub fn open_credit_account(
+        ctx: Context<OpenCreditAccount>,
+    ) -> Result<()> {
+        let credit = &mut ctx.accounts.credit_account;
+        
+        credit.owner = ctx.accounts.owner.key();
+        credit.mint = ctx.accounts.wale_mint.key();
+        credit.vault = ctx.accounts.vault.key();
+        credit.total_deposited = 0;
+        credit.total_spent = 0;
+        credit.spenders = Vec::new();
+        credit.created_at = Clock::get()?.unix_timestamp;
+        credit.bumps.credit = *ctx.bumps.get("credit_account").unwrap();
+        credit.bumps.vault = *ctx.bumps.get("vault").unwrap();
+        
+        Ok(())
+    }
+    
+    /// Add WALE to a credit account; anyone may top up
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn top_up_credit<'info>(
+        ctx: Context<'_, '_, '_, 'info, TopUpCredit<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidQuantity);
+        
+        let vault_before = ctx.accounts.vault.amount;
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            mint: ctx.accounts.wale_mint.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.wale_mint.decimals)?;
+        let amount = received(&mut ctx.accounts.vault, vault_before)?;
+        
+        let credit = &mut ctx.accounts.credit_account;
+        credit.total_deposited = credit.total_deposited.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(CreditToppedUpEvent {
+            credit: credit.key(),
+            funder: ctx.accounts.funder.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+    
+    /// Withdraw unused credit back to the owner
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn withdraw_credit<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawCredit<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidQuantity);
+        require!(amount <= ctx.accounts.vault.amount, ErrorCode::InsufficientCredit);
+        
+        let credit = &ctx.accounts.credit_account;
+        let seeds = &[
+            b"credit".as_ref(),
+            credit.owner.as_ref(),
+            credit.mint.as_ref(),
+            &[credit.bumps.credit],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.vault.to_account_info(),
+            mint: ctx.accounts.wale_mint.to_account_info(),
+            to: ctx.accounts.owner_token_account.to_account_info(),
+            authority: credit.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.wale_mint.decimals)?;
+        
+        emit!(CreditWithdrawnEvent {
+            credit: credit.key(),
+            owner: credit.owner,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+    
+    /// Let `spender`, e.g. an agent's key, record executions paid from this
+    /// credit account up to `daily_limit` per UTC day; a limit of 0 removes
+    /// the spender
+    pub fn set_credit_spender(
+        ctx: Context<ManageCredit>,
+        spender: Pubkey,
+        daily_limit: u64,
+    ) -> Result<()> {
+        let credit = &mut ctx.accounts.credit_account;
+        require!(spender != credit.owner, ErrorCode::UnauthorizedCreditSpender);
+        
+        let existing = credit.spenders.iter().position(|s| s.spender == spender);
+        match (existing, daily_limit) {
+            (Some(i), 0) => {
+                credit.spenders.remove(i);
+            }
+            (Some(i), _) => credit.spenders[i].daily_limit = daily_limit,
+            (None, 0) => {}
+            (None, _) => {
+                require!(
+                    credit.spenders.len() < CreditAccount::MAX_SPENDERS,
+                    ErrorCode::TooManyCreditSpenders
+                );
+                credit.spenders.push(CreditSpender {
+                    spender,
+                    daily_limit,
+                    day: 0,
+                    spent_today: 0,
+                });
+            }
+        }
+        
+        emit!(CreditSpenderUpdatedEvent {
+            credit: credit.key(),
+            spender,
+            daily_limit,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+    
+    /// Authorize `session_key` to record and update the signer's executions
+    /// of the templates in `scope` until `expiry`, drawing at most
+    /// `max_spend` from the signer's credit account
+    pub fn create_session_key(
+        ctx: Context<CreateSessionKey>,
+        scope: Vec<Pubkey>,
+        expiry: i64,
+        max_spend: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            !scope.is_empty() && scope.len() <= SessionKey::MAX_SCOPE,
+            ErrorCode::InvalidSessionScope
+        );
+        require!(
+            expiry > now && expiry - now <= SessionKey::MAX_DURATION,
+            ErrorCode::InvalidSessionExpiry
+        );
+        
+        let session = &mut ctx.accounts.session;
+        session.user = ctx.accounts.user.key();
+        session.session_key = ctx.accounts.session_key.key();
+        session.scope = scope;
+        session.expires_at = expiry;
+        session.max_spend = max_spend;
+        session.spent = 0;
+        session.created_at = now;
+        session.bumps.session = *ctx.bumps.get("session").unwrap();
+        
+        emit!(SessionKeyCreatedEvent {
+            user: session.user,
+            session_key: session.session_key,
+            scope: session.scope.clone(),
+            expires_at: expiry,
+            max_spend,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+    
+    /// Revoke a session key before it expires and reclaim its rent
+    pub fn revoke_session_key(
+        ctx: Context<RevokeSessionKey>,
+    ) -> Result<()> {
+        emit!(SessionKeyRevokedEvent {
+            user: ctx.accounts.user.key(),
+            session_key: ctx.accounts.session.session_key,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(template_id: String)]
+pub struct InitializeTemplate<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    #[account(
         mut,
-        constraint = template.creator == creator.key(),
+        seeds = [b"category", category.slug.as_bytes()],
+        bump = category.bumps.category,
+    )]
+    pub category: Account<'info, Category>,
+    
+    #[account(
+        init,
+        payer = creator,
+        space = TemplateAccount::space(&template_id),
+        seeds = [
+            b"template".as_ref(),
+            &id_seed(&template_id),
+            creator.key().as_ref(),
+        ],
+        bump
     )]
     pub template: Account<'info, TemplateAccount>,
+    
+    #[account(mut)]
+    pub category_index: Account<'info, TemplateIndex>,
+    
+    #[account(mut)]
+    pub creator_index: Account<'info, TemplateIndex>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(task_id: String)]
+pub struct RecordExecution<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub executor: Signer<'info>,
+    
+    /// CHECK: Wallet the execution is recorded for; its consent is checked
+    /// by the instruction
+    pub beneficiary: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"session", beneficiary.key().as_ref(), executor.key().as_ref()],
+        bump = session.bumps.session,
+    )]
+    pub session: Option<Account<'info, SessionKey>>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = ExecutionAccount::space(&task_id),
+        seeds = [
+            b"execution".as_ref(),
+            &id_seed(&task_id),
+            beneficiary.key().as_ref(),
+        ],
+        bump
+    )]
+    pub execution: Account<'info, ExecutionAccount>,
+    
+    #[account(mut)]
+    pub license: Option<Account<'info, License>>,
+    
+    pub template_version: Option<Account<'info, TemplateVersion>>,
+    
+    #[account(
+        seeds = [b"listing", template.key().as_ref()],
+        bump = listing.bumps.listing,
+    )]
+    pub listing: Option<Account<'info, Listing>>,
+    
+    #[account(
+        mut,
+        seeds = [b"credit", credit_account.owner.as_ref(), credit_account.mint.as_ref()],
+        bump = credit_account.bumps.credit,
+    )]
+    pub credit_account: Option<Account<'info, CreditAccount>>,
+    
+    #[account(mut)]
+    pub credit_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Mint of the credit account, needed for checked transfers
+    pub wale_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    /// CHECK: Instructions sysvar, read for ed25519 consent
+    #[account(address = ix_sysvar::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateExecution<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = execution.beneficiary_user == user.key()
+            || session.is_some()
+            @ ErrorCode::UnauthorizedExecutionUpdate,
+    )]
+    pub execution: Account<'info, ExecutionAccount>,
+    
+    #[account(
+        seeds = [b"session", execution.beneficiary_user.as_ref(), user.key().as_ref()],
+        bump = session.bumps.session,
+    )]
+    pub session: Option<Account<'info, SessionKey>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSessionKey<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    /// CHECK: Any key; it only has to sign later executions
+    pub session_key: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = SessionKey::space(),
+        seeds = [b"session", user.key().as_ref(), session_key.key().as_ref()],
+        bump
+    )]
+    pub session: Account<'info, SessionKey>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSessionKey<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"session", user.key().as_ref(), session.session_key.as_ref()],
+        bump = session.bumps.session,
+        close = user,
+    )]
+    pub session: Account<'info, SessionKey>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTemplate<'info> {
+    #[account(mut)]
+    pub updater: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = template.can_update(&updater.key()) @ ErrorCode::UnauthorizedTemplateUpdate,
+    )]
+    pub template: Account<'info, TemplateAccount>,
+}
+
+#[derive(Accounts)]
+pub struct QueueTemplateUpdate<'info> {
+    #[account(mut)]
+    pub updater: Signer<'info>,
+    
+    #[account(
+        constraint = template.can_update(&updater.key()) @ ErrorCode::UnauthorizedTemplateUpdate,
+    )]
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(
+        init,
+        payer = updater,
+        space = PendingTemplateUpdate::space(),
+        seeds = [b"pending_update", template.key().as_ref()],
+        bump
+    )]
+    pub pending_update: Account<'info, PendingTemplateUpdate>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTemplateUpdate<'info> {
+    pub canceller: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bumps.config,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        constraint = canceller.key() == config.guardian || canceller.key() == template.creator
+            @ ErrorCode::UnauthorizedGuardian,
+    )]
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"pending_update", template.key().as_ref()],
+        bump = pending_update.bumps.pending_update,
+        close = queued_by,
+    )]
+    pub pending_update: Account<'info, PendingTemplateUpdate>,
+    
+    /// CHECK: Receives the pending update's rent, checked against the account
+    #[account(mut, address = pending_update.queued_by)]
+    pub queued_by: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTemplateUpdate<'info> {
+    #[account(mut)]
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"pending_update", template.key().as_ref()],
+        bump = pending_update.bumps.pending_update,
+        close = queued_by,
+    )]
+    pub pending_update: Account<'info, PendingTemplateUpdate>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing", template.key().as_ref()],
+        bump = listing.bumps.listing,
+    )]
+    pub listing: Option<Account<'info, Listing>>,
+    
+    /// CHECK: Receives the pending update's rent, checked against the account
+    #[account(mut, address = pending_update.queued_by)]
+    pub queued_by: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManageTemplate<'info> {
+    pub creator: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = template.creator == creator.key() @ ErrorCode::UnauthorizedTemplateOwner,
+    )]
+    pub template: Account<'info, TemplateAccount>,
+}
+
+#[derive(Accounts)]
+pub struct TransferTemplateOwnership<'info> {
+    pub creator: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = template.creator == creator.key() @ ErrorCode::UnauthorizedTemplateOwner,
+    )]
+    pub template: Account<'info, TemplateAccount>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptTemplateOwnership<'info> {
+    pub new_owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = template.pending_owner == Some(new_owner.key()) @ ErrorCode::NotPendingOwner,
+    )]
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(mut)]
+    pub old_creator_index: Account<'info, TemplateIndex>,
+    
+    #[account(mut)]
+    pub new_creator_index: Account<'info, TemplateIndex>,
+}
+
+#[derive(Accounts)]
+pub struct CreateListing<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = template.creator == creator.key() @ ErrorCode::UnauthorizedTemplateOwner,
+    )]
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(
+        init,
+        payer = creator,
+        space = Listing::space(),
+        seeds = [b"listing", template.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateListing<'info> {
+    pub creator: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = template.creator == creator.key() @ ErrorCode::UnauthorizedTemplateOwner,
+    )]
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing", template.key().as_ref()],
+        bump = listing.bumps.listing,
+    )]
+    pub listing: Account<'info, Listing>,
+}
+
+#[derive(Accounts)]
+pub struct PurchaseLicense<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing", template.key().as_ref()],
+        bump = listing.bumps.listing,
+        constraint = listing.is_active @ ErrorCode::ListingInactive,
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = License::space(),
+        seeds = [b"license", listing.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub license: Account<'info, License>,
+    
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key(),
+        constraint = buyer_token_account.mint == listing.payment_mint @ ErrorCode::InvalidPaymentMint,
+    )]
+    pub buyer_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = listing.payment_mint)]
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RateTemplate<'info> {
+    #[account(mut)]
+    pub rater: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bumps.config,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(mut)]
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(
+        constraint = execution.template == template.key() @ ErrorCode::RatingNotEarned,
+        constraint = execution.beneficiary_user == rater.key() @ ErrorCode::RatingNotEarned,
+        constraint = execution.status == ExecutionStatus::Success @ ErrorCode::RatingNotEarned,
+    )]
+    pub execution: Account<'info, ExecutionAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = rater,
+        space = Rating::space(),
+        seeds = [b"rating", template.key().as_ref(), rater.key().as_ref()],
+        bump
+    )]
+    pub rating: Account<'info, Rating>,
+    
+    #[account(
+        mut,
+        seeds = [b"rating_stake", rater.key().as_ref()],
+        bump = rater_stake.bumps.rating_stake,
+    )]
+    pub rater_stake: Account<'info, RatingStake>,
+    
+    #[account(address = config.wale_mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LockRatingStake<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bumps.config,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = RatingStake::space(),
+        seeds = [b"rating_stake", owner.key().as_ref()],
+        bump
+    )]
+    pub rating_stake: Account<'info, RatingStake>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [b"rating_stake_vault", rating_stake.key().as_ref()],
+        bump,
+        token::mint = wale_mint,
+        token::authority = rating_stake,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key(),
+        constraint = owner_token_account.mint == wale_mint.key() @ ErrorCode::InvalidPaymentMint,
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = config.wale_mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UnlockRatingStake<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"rating_stake", owner.key().as_ref()],
+        bump = rating_stake.bumps.rating_stake,
+    )]
+    pub rating_stake: Account<'info, RatingStake>,
+    
+    #[account(mut, address = rating_stake.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = vault.mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key(),
+        constraint = owner_token_account.mint == vault.mint @ ErrorCode::InvalidPaymentMint,
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetTemplateVerified<'info> {
+    pub curator: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bumps.config,
+        constraint = config.curator == curator.key() @ ErrorCode::UnauthorizedCurator,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(mut)]
+    pub template: Account<'info, TemplateAccount>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterAuditor<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bumps.config,
+        constraint = config.admin == admin.key() @ ErrorCode::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    /// CHECK: Only used as the auditor's signing key
+    pub auditor_authority: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = Auditor::space(),
+        seeds = [b"auditor", auditor_authority.key().as_ref()],
+        bump
+    )]
+    pub auditor: Account<'info, Auditor>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAuditorActive<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bumps.config,
+        constraint = config.admin == admin.key() @ ErrorCode::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"auditor", auditor.authority.as_ref()],
+        bump = auditor.bumps.auditor,
+    )]
+    pub auditor: Account<'info, Auditor>,
+}
+
+#[derive(Accounts)]
+pub struct PublishTemplateVersion<'info> {
+    #[account(mut)]
+    pub updater: Signer<'info>,
+    
+    #[account(
+        constraint = template.can_update(&updater.key()) @ ErrorCode::UnauthorizedTemplateUpdate,
+    )]
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(
+        init,
+        payer = updater,
+        space = TemplateVersion::space(),
+        seeds = [
+            b"template_version",
+            template.key().as_ref(),
+            &id_seed(&template.template_version),
+            &template.metadata.digest(),
+        ],
+        bump
+    )]
+    pub template_version: Account<'info, TemplateVersion>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AttestTemplateVersion<'info> {
+    #[account(mut)]
+    pub auditor_authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"auditor", auditor_authority.key().as_ref()],
+        bump = auditor.bumps.auditor,
+        constraint = auditor.is_active @ ErrorCode::AuditorInactive,
+    )]
+    pub auditor: Account<'info, Auditor>,
+    
+    #[account(mut)]
+    pub template_version: Account<'info, TemplateVersion>,
+    
+    #[account(
+        init,
+        payer = auditor_authority,
+        space = Attestation::space(),
+        seeds = [
+            b"attestation",
+            template_version.key().as_ref(),
+            auditor_authority.key().as_ref(),
+        ],
+        bump
+    )]
+    pub attestation: Account<'info, Attestation>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateBounty<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bumps.config,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        constraint = template.creator == creator.key() @ ErrorCode::UnauthorizedTemplateOwner,
+    )]
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(
+        init,
+        payer = creator,
+        space = Bounty::space(),
+        seeds = [b"bounty", template.key().as_ref()],
+        bump
+    )]
+    pub bounty: Account<'info, Bounty>,
+    
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"bounty_vault", bounty.key().as_ref()],
+        bump,
+        token::mint = wale_mint,
+        token::authority = bounty,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = config.wale_mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FundBounty<'info> {
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    
+    #[account(
+        constraint = template.key() == bounty.template,
+        constraint = funder.key() == template.creator @ ErrorCode::UnauthorizedBountyFunder,
+    )]
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"bounty", bounty.template.as_ref()],
+        bump = bounty.bumps.bounty,
+    )]
+    pub bounty: Account<'info, Bounty>,
+    
+    #[account(
+        init_if_needed,
+        payer = funder,
+        space = BountyDeposit::space(),
+        seeds = [b"bounty_deposit", bounty.key().as_ref(), funder.key().as_ref()],
+        bump
+    )]
+    pub deposit: Account<'info, BountyDeposit>,
+    
+    #[account(mut, address = bounty.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = vault.mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = funder_token_account.owner == funder.key(),
+    )]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bumps.config,
+        constraint = config.admin == admin.key() @ ErrorCode::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    /// CHECK: Data-less PDA owning the treasury vault
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"treasury_vault"],
+        bump,
+        token::mint = wale_mint,
+        token::authority = treasury,
+    )]
+    pub treasury_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = config.wale_mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FundBountyFromTreasury<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bumps.config,
+        constraint = config.admin == admin.key() @ ErrorCode::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"bounty", bounty.template.as_ref()],
+        bump = bounty.bumps.bounty,
+    )]
+    pub bounty: Account<'info, Bounty>,
+    
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = BountyDeposit::space(),
+        seeds = [b"bounty_deposit", bounty.key().as_ref(), treasury.key().as_ref()],
+        bump
+    )]
+    pub deposit: Account<'info, BountyDeposit>,
+    
+    #[account(mut, address = bounty.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = vault.mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Data-less PDA owning the treasury vault
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"treasury_vault"], bump)]
+    pub treasury_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(commitment: [u8; 32])]
+pub struct SubmitBountyReport<'info> {
+    #[account(mut)]
+    pub researcher: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"bounty", bounty.template.as_ref()],
+        bump = bounty.bumps.bounty,
+    )]
+    pub bounty: Account<'info, Bounty>,
+    
+    #[account(
+        init,
+        payer = researcher,
+        space = BountyReport::space(),
+        seeds = [b"bounty_report", bounty.key().as_ref(), commitment.as_ref()],
+        bump
+    )]
+    pub report: Account<'info, BountyReport>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveBountyReport<'info> {
+    pub resolver: Signer<'info>,
+    
+    #[account(
+        constraint = template.key() == bounty.template,
+        constraint = bounty.arbiters.contains(&resolver.key()) && resolver.key() != template.creator
+            @ ErrorCode::UnauthorizedBountyResolver,
+    )]
+    pub template: Account<'info, TemplateAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"bounty", bounty.template.as_ref()],
+        bump = bounty.bumps.bounty,
+    )]
+    pub bounty: Account<'info, Bounty>,
+    
+    #[account(
+        mut,
+        constraint = report.bounty == bounty.key(),
+        constraint = report.status == ReportStatus::Pending @ ErrorCode::ReportAlreadyResolved,
+    )]
+    pub report: Account<'info, BountyReport>,
+    
+    #[account(mut, address = bounty.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = vault.mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub researcher_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RefundBounty<'info> {
+    #[account(
+        mut,
+        seeds = [b"bounty", bounty.template.as_ref()],
+        bump = bounty.bumps.bounty,
+    )]
+    pub bounty: Account<'info, Bounty>,
+    
+    #[account(
+        mut,
+        seeds = [b"bounty_deposit", bounty.key().as_ref(), deposit.funder.as_ref()],
+        bump = deposit.bumps.deposit,
+    )]
+    pub deposit: Account<'info, BountyDeposit>,
+    
+    #[account(mut, address = bounty.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = vault.mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = funder_token_account.owner == deposit.funder,
+    )]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct OpenCreditAccount<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bumps.config,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = CreditAccount::space(),
+        seeds = [b"credit", owner.key().as_ref(), wale_mint.key().as_ref()],
+        bump
+    )]
+    pub credit_account: Account<'info, CreditAccount>,
+    
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"credit_vault", credit_account.key().as_ref()],
+        bump,
+        token::mint = wale_mint,
+        token::authority = credit_account,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = config.wale_mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct TopUpCredit<'info> {
+    pub funder: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"credit", credit_account.owner.as_ref(), credit_account.mint.as_ref()],
+        bump = credit_account.bumps.credit,
+    )]
+    pub credit_account: Account<'info, CreditAccount>,
+    
+    #[account(mut, address = credit_account.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = credit_account.mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = funder_token_account.owner == funder.key(),
+    )]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCredit<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        seeds = [b"credit", owner.key().as_ref(), credit_account.mint.as_ref()],
+        bump = credit_account.bumps.credit,
+        has_one = owner,
+    )]
+    pub credit_account: Account<'info, CreditAccount>,
+    
+    #[account(mut, address = credit_account.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = credit_account.mint)]
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key(),
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ManageCredit<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"credit", owner.key().as_ref(), credit_account.mint.as_ref()],
+        bump = credit_account.bumps.credit,
+        has_one = owner,
+    )]
+    pub credit_account: Account<'info, CreditAccount>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub upgrade_authority: Signer<'info>,
+    
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::Mcp>,
+    
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(upgrade_authority.key())
+            @ ErrorCode::UnauthorizedAdmin,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = ProtocolConfig::space(),
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    pub wale_mint: InterfaceAccount<'info, Mint>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bumps.config,
+        constraint = config.admin == admin.key() @ ErrorCode::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(slug: String)]
+pub struct CreateCategory<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bumps.config,
+        constraint = config.admin == admin.key() @ ErrorCode::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = Category::space(),
+        seeds = [b"category", slug.as_bytes()],
+        bump
+    )]
+    pub category: Account<'info, Category>,
+    
+    pub parent: Option<Account<'info, Category>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCategory<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bumps.config,
+        constraint = config.admin == admin.key() @ ErrorCode::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"category", category.slug.as_bytes()],
+        bump = category.bumps.category,
+    )]
+    pub category: Account<'info, Category>,
+    
+    pub parent: Option<Account<'info, Category>>,
+}
+
+#[derive(Accounts)]
+#[instruction(kind: IndexKind, key: [u8; 32], page: u32)]
+pub struct CreateIndexPage<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = TemplateIndex::space(),
+        seeds = [
+            b"index".as_ref(),
+            &[kind as u8],
+            key.as_ref(),
+            &page.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub index: Account<'info, TemplateIndex>,
+    
+    pub previous: Option<Account<'info, TemplateIndex>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(Default)]
+pub struct ProtocolConfig {
+    pub admin: Pubkey,                // Admin allowed to manage the registry
+    pub curator: Pubkey,              // Grants and revokes verified badges
+    pub guardian: Pubkey,             // May cancel queued template updates
+    pub wale_mint: Pubkey,            // WALE mint used for rating stake
+    pub min_rating_stake: u64,        // Minimum locked WALE to rate
+    pub category_count: u64,          // Number of registered categories
+    pub bumps: ConfigBumps,           // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ConfigBumps {
+    pub config: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct Category {
+    pub slug: String,                 // Canonical lowercase slug (defi, dao, ...)
+    pub display_name: String,         // Human readable name
+    pub parent: Option<Pubkey>,       // Parent category, if any
+    pub is_active: bool,              // Whether new templates may use it
+    pub template_count: u64,          // Templates registered in this category
+    pub created_at: i64,              // Creation timestamp
+    pub bumps: CategoryBumps,         // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct CategoryBumps {
+    pub category: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct TemplateAccount {
+    pub template_id: String,          // Unique identifier
+    pub template_name: String,        // Display name
+    pub template_version: String,     // Version string
+    pub category: Pubkey,             // Category registry account
+    pub tags: Vec<String>,            // Discovery tags (governance, yield, ...)
+    pub creator: Pubkey,              // Creator's public key
+    pub metadata: ContentRef,         // Content-addressed template body
+    pub created_at: i64,              // Creation timestamp
+    pub updated_at: i64,              // Last update timestamp
+    pub usage_count: u64,             // Number of executions
+    pub is_active: bool,              // Active status
+    pub pending_owner: Option<Pubkey>, // Owner nominated by a pending transfer
+    pub maintainers: Vec<Pubkey>,     // Co-authors allowed to update the template
+    pub revenue_splits: Vec<RevenueShare>, // Payout table in basis points
+    pub requires_license: bool,       // Executions need a marketplace license
+    pub rating_count: u64,            // Number of distinct raters
+    pub rating_weight_total: u64,     // Sum of rating weights
+    pub rating_score_total: u64,      // Sum of score * weight
+    pub verified: bool,               // Verified badge granted by the curator
+    pub audit_policy: AuditPolicy,    // Attestations required to execute
+    pub update_delay: i64,            // Seconds updates wait in the queue, 0 for none
+    pub bumps: TemplateBumps,         // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct TemplateBumps {
+    pub template: u8,
+}
+
+/// How a marketplace listing charges for its template
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default)]
+pub enum PricingModel {
+    #[default]
+    Free,
+    /// Price per recorded execution, bought in bundles
+    PerExecution { price: u64 },
+    /// Price per period of `period` seconds
+    Subscription { price: u64, period: i64 },
+    /// Single payment for unlimited use
+    OneTimeLicense { price: u64 },
+}
+
+
+impl PricingModel {
+    pub fn space() -> usize {
+        1 + // Variant
+            8 + // Price
+            8 // Period
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            PricingModel::Free => {}
+            PricingModel::PerExecution { price } | PricingModel::OneTimeLicense { price } => {
+                require!(*price > 0, ErrorCode::InvalidPricing);
+            }
+            PricingModel::Subscription { price, period } => {
+                require!(*price > 0 && *period > 0, ErrorCode::InvalidPricing);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A set of template changes, applied directly or through the update queue
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct TemplateUpdate {
+    pub template_name: Option<String>,
+    pub template_version: Option<String>,
+    pub metadata: Option<ContentRef>,
+    pub is_active: Option<bool>,
+    pub tags: Option<Vec<String>>,
+    pub update_delay: Option<i64>,
+    pub audit_policy: Option<AuditPolicy>,
+    pub maintainers: Option<Vec<Pubkey>>,
+    pub revenue_splits: Option<Vec<RevenueShare>>,
+    pub pending_owner: Option<Option<Pubkey>>,
+    pub listing_pricing: Option<PricingModel>,
+    pub listing_active: Option<bool>,
+}
+
+impl TemplateUpdate {
+    pub const MAX_DELAY: i64 = 30 * 24 * 60 * 60;
+
+    pub fn space() -> usize {
+        1 + 4 + 100 + // Template name
+            1 + 4 + 20 + // Template version
+            1 + ContentRef::max_space() + // Metadata
+            1 + 1 + // Is active
+            1 + 4 + TemplateAccount::MAX_TAGS * (4 + TemplateAccount::MAX_TAG_LEN) + // Tags
+            1 + 8 + // Update delay
+            1 + AuditPolicy::space() + // Audit policy
+            1 + 4 + 32 * TemplateAccount::MAX_MAINTAINERS + // Maintainers
+            1 + 4 + RevenueShare::space() * RevenueShare::MAX_SPLITS + // Revenue splits
+            1 + 1 + 32 + // Pending owner
+            1 + PricingModel::space() + // Listing pricing
+            1 + 1 // Listing active
+    }
+
+    /// Whether the update touches fields only the template owner controls
+    pub fn requires_owner(&self) -> bool {
+        self.update_delay.is_some()
+            || self.audit_policy.is_some()
+            || self.maintainers.is_some()
+            || self.revenue_splits.is_some()
+            || self.pending_owner.is_some()
+            || self.touches_listing()
+    }
+
+    /// Check `updater` may make this update to `template`
+    pub fn authorize(&self, template: &TemplateAccount, updater: &Pubkey) -> Result<()> {
+        if self.requires_owner() {
+            require!(template.creator == *updater, ErrorCode::UnauthorizedTemplateOwner);
+        } else {
+            require!(template.can_update(updater), ErrorCode::UnauthorizedTemplateUpdate);
+        }
+        Ok(())
+    }
+
+    pub fn touches_listing(&self) -> bool {
+        self.listing_pricing.is_some() || self.listing_active.is_some()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some(ref name) = self.template_name {
+            require!(name.len() <= 100, ErrorCode::NameTooLong);
+        }
+        if let Some(ref version) = self.template_version {
+            require!(version.len() <= 20, ErrorCode::VersionTooLong);
+        }
+        if let Some(ref metadata) = self.metadata {
+            metadata.validate()?;
+        }
+        if let Some(ref tags) = self.tags {
+            TemplateAccount::validate_tags(tags)?;
+        }
+        if let Some(delay) = self.update_delay {
+            require!((0..=Self::MAX_DELAY).contains(&delay), ErrorCode::InvalidTimelockDelay);
+        }
+        if let Some(ref policy) = self.audit_policy {
+            policy.validate()?;
+        }
+        if let Some(ref maintainers) = self.maintainers {
+            TemplateAccount::validate_maintainers(maintainers)?;
+        }
+        if let Some(ref splits) = self.revenue_splits {
+            RevenueShare::validate(splits)?;
+        }
+        if let Some(ref pricing) = self.listing_pricing {
+            pricing.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// A template update waiting out the template's delay
+#[account]
+#[derive(Default)]
+pub struct PendingTemplateUpdate {
+    pub template: Pubkey,             // Template to update
+    pub update: TemplateUpdate,       // Changes to apply
+    pub eta: i64,                     // Earliest execution time
+    pub queued_by: Pubkey,            // Owner or maintainer who queued it
+    pub queued_at: i64,               // Queue timestamp
+    pub bumps: PendingUpdateBumps,    // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct PendingUpdateBumps {
+    pub pending_update: u8,
+}
+
+/// Attestation requirements a template sets for its own executions
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct AuditPolicy {
+    pub required_attestations: u8,    // Valid attestations needed, 0 to disable
+    pub reject_critical: bool,        // Ignore attestations reporting critical issues
+}
+
+impl AuditPolicy {
+    pub const MAX_REQUIRED_ATTESTATIONS: usize = 5;
+
+    pub fn space() -> usize {
+        1 + // Required attestations
+            1 // Reject critical
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            usize::from(self.required_attestations) <= Self::MAX_REQUIRED_ATTESTATIONS,
+            ErrorCode::InvalidAuditPolicy
+        );
+        Ok(())
+    }
+
+    /// Whether this policy is at least as strict as `other`
+    pub fn is_at_least(&self, other: &AuditPolicy) -> bool {
+        self.required_attestations >= other.required_attestations
+            && (self.reject_critical || !other.reject_critical)
+    }
+}
+
+/// Findings of an audit, by severity
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct SeveritySummary {
+    pub critical: u8,
+    pub high: u8,
+    pub medium: u8,
+    pub low: u8,
+}
+
+/// An auditor registered by the protocol admin
+#[account]
+#[derive(Default)]
+pub struct Auditor {
+    pub authority: Pubkey,            // Key that signs attestations
+    pub name: String,                 // Auditor display name
+    pub is_active: bool,              // Whether attestations count
+    pub attestation_count: u64,       // Attestations issued
+    pub registered_at: i64,           // Registration timestamp
+    pub bumps: AuditorBumps,          // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct AuditorBumps {
+    pub auditor: u8,
+}
+
+/// Immutable snapshot of a template version that audits refer to
+#[account]
+#[derive(Default)]
+pub struct TemplateVersion {
+    pub template: Pubkey,             // Template PDA account
+    pub version: String,              // Version string at publication
+    pub content_digest: [u8; 32],     // SHA-256 of the template body
+    pub attestation_count: u64,       // Attestations recorded
+    pub published_at: i64,            // Publication timestamp
+    pub bumps: TemplateVersionBumps,  // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct TemplateVersionBumps {
+    pub template_version: u8,
+}
+
+/// An auditor's review of one template version
+#[account]
+#[derive(Default)]
+pub struct Attestation {
+    pub auditor: Pubkey,              // Auditor authority
+    pub template_version: Pubkey,     // Attested template version
+    pub report_hash: [u8; 32],        // SHA-256 of the audit report
+    pub severity: SeveritySummary,    // Findings by severity
+    pub expires_at: i64,              // After this the attestation stops counting
+    pub attested_at: i64,             // Attestation timestamp
+    pub bumps: AttestationBumps,      // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct AttestationBumps {
+    pub attestation: u8,
+}
+
+/// WALE bug bounty escrow for one template
+#[account]
+#[derive(Default)]
+pub struct Bounty {
+    pub template: Pubkey,             // Template covered by the bounty
+    pub vault: Pubkey,                // Token account holding the funds
+    pub arbiters: Vec<Pubkey>,        // Keys that resolve reports, never the creator
+    pub funded_total: u64,            // Total deposited
+    pub paid_total: u64,              // Total paid out to researchers
+    pub refund_pool: u64,             // Vault balance frozen at the first refund
+    pub refunds_started: bool,        // Whether `refund_pool` has been frozen
+    pub report_count: u64,            // Reports submitted
+    pub expires_at: i64,              // No reports or payouts after this
+    pub created_at: i64,              // Creation timestamp
+    pub bumps: BountyBumps,           // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct BountyBumps {
+    pub bounty: u8,
+    pub vault: u8,
+}
+
+/// One funder's contribution to a bounty
+#[account]
+#[derive(Default)]
+pub struct BountyDeposit {
+    pub bounty: Pubkey,               // Funded bounty
+    pub funder: Pubkey,               // Funder's public key, or the treasury PDA
+    pub amount: u64,                  // Total deposited by this funder
+    pub refunded: bool,               // Whether the expiry refund was claimed
+    pub bumps: BountyDepositBumps,    // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct BountyDepositBumps {
+    pub deposit: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportStatus {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+}
+
+
+/// A researcher's hash-committed bug report
+#[account]
+#[derive(Default)]
+pub struct BountyReport {
+    pub bounty: Pubkey,               // Bounty the report was filed against
+    pub researcher: Pubkey,           // Researcher's public key
+    pub commitment: [u8; 32],         // SHA-256 of report and salt
+    pub status: ReportStatus,         // Review status
+    pub payout: u64,                  // Amount paid on approval
+    pub submitted_at: i64,            // Submission timestamp
+    pub resolved_at: i64,             // Approval or rejection timestamp
+    pub bumps: BountyReportBumps,     // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct BountyReportBumps {
+    pub report: u8,
+}
+
+/// A rater's score for a template
+#[account]
+#[derive(Default)]
+pub struct Rating {
+    pub template: Pubkey,             // Rated template
+    pub rater: Pubkey,                // Rater's public key
+    pub execution: Pubkey,            // Successful execution backing the rating
+    pub score: u8,                    // Score from 1 to 5
+    pub weight: u64,                  // Stake weight applied to the score
+    pub review_hash: Option<[u8; 32]>, // SHA-256 of the off-chain review text
+    pub rated_at: i64,                // Last rating timestamp
+    pub bumps: RatingBumps,           // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct RatingBumps {
+    pub rating: u8,
+}
+
+/// WALE locked by a rater; its size weighs the rater's ratings
+#[account]
+#[derive(Default)]
+pub struct RatingStake {
+    pub owner: Pubkey,                // Rater's public key
+    pub vault: Pubkey,                // Token account holding the locked WALE
+    pub amount: u64,                  // WALE locked
+    pub locked_until: i64,            // Withdrawals allowed from this time
+    pub bumps: RatingStakeBumps,      // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct RatingStakeBumps {
+    pub rating_stake: u8,
+    pub vault: u8,
+}
+
+/// Marketplace listing for a template
+#[account]
+#[derive(Default)]
+pub struct Listing {
+    pub template: Pubkey,             // Template being sold
+    pub payment_mint: Pubkey,         // Mint accepted as payment (WALE)
+    pub pricing: PricingModel,        // Pricing model
+    pub is_active: bool,              // Whether new licenses can be bought
+    pub sales_count: u64,             // Number of purchases
+    pub revenue: u64,                 // Total payments received
+    pub created_at: i64,              // Creation timestamp
+    pub bumps: ListingBumps,          // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ListingBumps {
+    pub listing: u8,
+}
+
+/// A buyer's right to execute a listed template
+#[account]
+#[derive(Default)]
+pub struct License {
+    pub listing: Pubkey,              // Listing the license was bought from
+    pub template: Pubkey,             // Licensed template
+    pub holder: Pubkey,               // License holder
+    pub perpetual: bool,              // One-time license, never expires
+    pub expires_at: i64,              // Subscription expiry (0 if none)
+    pub executions_remaining: u64,    // Prepaid per-execution uses
+    pub purchased_at: i64,            // Last purchase timestamp
+    pub bumps: LicenseBumps,          // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct LicenseBumps {
+    pub license: u8,
+}
+
+/// Prepaid WALE that pays for per-execution templates
+#[account]
+#[derive(Default)]
+pub struct CreditAccount {
+    pub owner: Pubkey,                // Owner who can withdraw and manage spenders
+    pub mint: Pubkey,                 // Credit mint (WALE)
+    pub vault: Pubkey,                // Token account holding the credit
+    pub total_deposited: u64,         // Total topped up
+    pub total_spent: u64,             // Total drawn by executions
+    pub spenders: Vec<CreditSpender>, // Delegated spenders besides the owner
+    pub created_at: i64,              // Creation timestamp
+    pub bumps: CreditBumps,           // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct CreditBumps {
+    pub credit: u8,
+    pub vault: u8,
+}
+
+/// A key allowed to spend from someone else's credit account
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct CreditSpender {
+    pub spender: Pubkey,
+    pub daily_limit: u64,
+    pub day: i64,
+    pub spent_today: u64,
+}
+
+/// An ephemeral key allowed to act for a user on specific templates
+#[account]
+#[derive(Default)]
+pub struct SessionKey {
+    pub user: Pubkey,                 // Wallet the session acts for
+    pub session_key: Pubkey,          // Key that signs for the session
+    pub scope: Vec<Pubkey>,           // Templates the session may execute
+    pub expires_at: i64,              // Session is unusable from this time
+    pub max_spend: u64,               // Credit the session may draw in total
+    pub spent: u64,                   // Credit drawn so far
+    pub created_at: i64,              // Creation timestamp
+    pub bumps: SessionKeyBumps,       // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct SessionKeyBumps {
+    pub session: u8,
+}
+
+/// One recipient's share of template revenue
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct RevenueShare {
+    pub recipient: Pubkey,
+    pub bps: u16,
+}
+
+impl RevenueShare {
+    pub const MAX_SPLITS: usize = 8;
+    pub const TOTAL_BPS: u16 = 10_000;
+
+    pub fn space() -> usize {
+        32 + // Recipient
+            2 // Basis points
+    }
+
+    pub fn validate(splits: &[RevenueShare]) -> Result<()> {
+        if splits.is_empty() {
+            return Ok(());
+        }
+        require!(splits.len() <= Self::MAX_SPLITS, ErrorCode::TooManyRevenueSplits);
+        
+        let mut total: u32 = 0;
+        for (i, split) in splits.iter().enumerate() {
+            require!(split.bps > 0, ErrorCode::InvalidRevenueSplits);
+            require!(
+                !splits[..i].iter().any(|other| other.recipient == split.recipient),
+                ErrorCode::InvalidRevenueSplits
+            );
+            total += u32::from(split.bps);
+        }
+        require!(total == u32::from(Self::TOTAL_BPS), ErrorCode::InvalidRevenueSplits);
+        Ok(())
+    }
+}
+
+/// Where a template body lives and how to check it.
+///
+/// Every off-chain variant pins a SHA-256 digest so the body fetched from a
+/// gateway or mirror can be verified against the on-chain record.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum ContentRef {
+    /// Binary CIDv1 with the raw codec and a sha2-256 multihash
+    Ipfs { cid: Vec<u8> },
+    /// Arweave transaction id plus the SHA-256 of the stored data
+    Arweave { tx_id: [u8; 32], sha256: [u8; 32] },
+    /// Compressed template body stored directly in the account
+    Inline { data: Vec<u8> },
+    /// HTTPS location plus the SHA-256 of the served body
+    Http { url: String, sha256: [u8; 32] },
+}
+
+impl Default for ContentRef {
+    fn default() -> Self {
+        ContentRef::Inline { data: Vec::new() }
+    }
+}
+
+/// Which discovery index a page belongs to
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexKind {
+    #[default]
+    Category,
+    Tag,
+    Creator,
+}
+
+
+/// One page of template pubkeys for a category, tag or creator
+///
+/// Pages live at `["index", kind, key, page]`, where `key` is the category
+/// or creator pubkey, or the SHA-256 of the tag. Clients fetch pages 0..n
+/// with `getMultipleAccounts` until one is missing.
+#[account]
+#[derive(Default)]
+pub struct TemplateIndex {
+    pub kind: IndexKind,              // Index kind
+    pub key: [u8; 32],                // Category/creator pubkey or tag hash
+    pub page: u32,                    // Page number, starting at 0
+    pub templates: Vec<Pubkey>,       // Template accounts on this page
+    pub bumps: IndexBumps,            // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct IndexBumps {
+    pub index: u8,
+}
+
+impl ContentRef {
+    pub const MAX_INLINE_LEN: usize = 1024;
+    pub const MAX_URL_LEN: usize = 200;
+    pub const MAX_CID_LEN: usize = 64;
+
+    /// CIDv1 version byte
+    const CID_V1: u64 = 0x01;
+    /// Multicodec for raw bytes; other codecs such as dag-pb hash an
+    /// encoded block rather than the body itself
+    const CODEC_RAW: u64 = 0x55;
+    /// Multihash code for sha2-256
+    const MULTIHASH_SHA2_256: u64 = 0x12;
+
+    /// Largest serialized size of any variant (the inline body)
+    pub fn max_space() -> usize {
+        1 + 4 + Self::MAX_INLINE_LEN
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            ContentRef::Ipfs { cid } => {
+                require!(cid.len() <= Self::MAX_CID_LEN, ErrorCode::InvalidCid);
+                Self::cid_sha256(cid).ok_or(ErrorCode::InvalidCid)?;
+            }
+            ContentRef::Arweave { tx_id, .. } => {
+                require!(tx_id.iter().any(|b| *b != 0), ErrorCode::InvalidArweaveTxId);
+            }
+            ContentRef::Inline { data } => {
+                require!(data.len() <= Self::MAX_INLINE_LEN, ErrorCode::MetadataTooLarge);
+            }
+            ContentRef::Http { url, .. } => {
+                require!(url.len() <= Self::MAX_URL_LEN, ErrorCode::InvalidContentUrl);
+                require!(url.starts_with("https://"), ErrorCode::InvalidContentUrl);
+                require!(url.len() > "https://".len(), ErrorCode::InvalidContentUrl);
+            }
+        }
+        Ok(())
+    }
+
+    /// SHA-256 of the template body wherever it lives
+    pub fn digest(&self) -> [u8; 32] {
+        match self {
+            ContentRef::Inline { data } => {
+                anchor_lang::solana_program::hash::hash(data).to_bytes()
+            }
+            // Validated refs always carry a digest
+            _ => self.sha256().unwrap_or_default(),
+        }
+    }
+
+    /// Expected SHA-256 of the off-chain body, `None` for inline content
+    pub fn sha256(&self) -> Option<[u8; 32]> {
+        match self {
+            ContentRef::Ipfs { cid } => Self::cid_sha256(cid),
+            ContentRef::Arweave { sha256, .. } => Some(*sha256),
+            ContentRef::Inline { .. } => None,
+            ContentRef::Http { sha256, .. } => Some(*sha256),
+        }
+    }
+
+    /// Parse `<version><codec><hash-code><hash-len><digest>` and return the
+    /// digest when the CID is a raw-codec v1 CID with a 32-byte sha2-256
+    /// multihash, i.e. when the digest is the SHA-256 of the body itself.
+    fn cid_sha256(cid: &[u8]) -> Option<[u8; 32]> {
+        let mut rest = cid;
+        let version = read_varint(&mut rest)?;
+        let codec = read_varint(&mut rest)?;
+        let hash_code = read_varint(&mut rest)?;
+        let hash_len = read_varint(&mut rest)?;
+
+        if version != Self::CID_V1 || codec != Self::CODEC_RAW {
+            return None;
+        }
+        if hash_code != Self::MULTIHASH_SHA2_256 {
+            return None;
+        }
+        if hash_len != 32 || rest.len() != 32 {
+            return None;
+        }
+
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(rest);
+        Some(digest)
+    }
+}
+
+/// Seed for a human-readable id
+///
+/// Template and task ids may be up to 64 bytes but a single PDA seed is
+/// capped at 32, so PDAs are keyed by the SHA-256 of the id. The readable
+/// id is still stored on the account.
+pub fn id_seed(id: &str) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hash(id.as_bytes()).to_bytes()
+}
+
+/// Address of the template PDA for `template_id` registered by `creator`
+pub fn template_address(template_id: &str, creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"template", &id_seed(template_id), creator.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Address of the execution PDA for `task_id` recorded for `beneficiary`
+pub fn execution_address(task_id: &str, beneficiary: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"execution", &id_seed(task_id), beneficiary.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Check that an ed25519 program instruction earlier in the transaction
+/// verified `signer`'s signature over `message`
+fn verify_ed25519_consent(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current = ix_sysvar::load_current_index_checked(instructions)?;
+    for index in 0..current {
+        let instruction = ix_sysvar::load_instruction_at_checked(usize::from(index), instructions)?;
+        if instruction.program_id == ed25519_program::ID
+            && ed25519_covers(&instruction.data, signer, message)
+        {
+            return Ok(());
+        }
+    }
+    err!(ErrorCode::MissingUserConsent)
+}
+
+/// Whether ed25519 program instruction data includes a signature by
+/// `signer` over `message`
+///
+/// Only entries whose key and message live in the ed25519 instruction
+/// itself count, so what's compared here is exactly what the precompile
+/// verified.
+fn ed25519_covers(data: &[u8], signer: &Pubkey, message: &[u8]) -> bool {
+    // u8 count and u8 padding, then seven u16 offsets per signature
+    const HEADER_LEN: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+    const THIS_INSTRUCTION: u16 = u16::MAX;
+    
+    let read_u16 = |at: usize| data.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+    let slice = |offset: u16, len: usize| data.get(usize::from(offset)..usize::from(offset) + len);
+    let count = data.first().copied().map_or(0, usize::from);
+    
+    (0..count).any(|i| {
+        let start = HEADER_LEN + i * OFFSETS_LEN;
+        let offsets: Option<Vec<u16>> = (0..7).map(|field| read_u16(start + 2 * field)).collect();
+        match offsets.as_deref() {
+            Some(&[_, signature_ix, key_offset, key_ix, message_offset, message_len, message_ix]) => {
+                signature_ix == THIS_INSTRUCTION
+                    && key_ix == THIS_INSTRUCTION
+                    && message_ix == THIS_INSTRUCTION
+                    && slice(key_offset, 32) == Some(signer.as_ref())
+                    && slice(message_offset, usize::from(message_len)) == Some(message)
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Check that putting `category` under `parent` keeps the category tree
+/// acyclic and at most `Category::MAX_DEPTH` ancestors deep
+///
+/// `ancestors` holds the parent's own ancestors, nearest first, up to the
+/// root; each is checked against the previous one's `parent` field.
+fn check_category_parent<'info>(
+    category: &Pubkey,
+    parent: &Account<'info, Category>,
+    ancestors: &[AccountInfo<'info>],
+) -> Result<()> {
+    require!(parent.key() != *category, ErrorCode::InvalidParentCategory);
+    
+    let mut depth = 1;
+    let mut next = parent.parent;
+    let mut ancestors = ancestors.iter();
+    while let Some(expected) = next {
+        require!(expected != *category, ErrorCode::InvalidParentCategory);
+        depth += 1;
+        require!(depth <= Category::MAX_DEPTH, ErrorCode::CategoryTooDeep);
+        
+        let info = ancestors.next().ok_or(ErrorCode::CategoryAncestorsMismatch)?;
+        require!(info.key() == expected, ErrorCode::CategoryAncestorsMismatch);
+        let ancestor: Account<'info, Category> = Account::try_from(info)?;
+        next = ancestor.parent;
+    }
+    
+    Ok(())
+}
+
+/// Apply a validated update to a template, moving it between tag index
+/// pages in `index_pages` when its tags change
+fn apply_template_update<'info>(
+    template: &mut Account<'info, TemplateAccount>,
+    listing: Option<&mut Account<'info, Listing>>,
+    update: TemplateUpdate,
+    index_pages: &[AccountInfo<'info>],
+) -> Result<()> {
+    let touches_listing = update.touches_listing();
+    
+    if let Some(name) = update.template_name {
+        template.template_name = name;
+    }
+    
+    if let Some(version) = update.template_version {
+        template.template_version = version;
+    }
+    
+    if let Some(metadata) = update.metadata {
+        template.metadata = metadata;
+    }
+    
+    if let Some(active) = update.is_active {
+        template.is_active = active;
+    }
+    
+    if let Some(new_tags) = update.tags {
+        let template_key = template.key();
+        let removed: Vec<&String> = template.tags.iter()
+            .filter(|tag| !new_tags.contains(tag))
+            .collect();
+        let added: Vec<&String> = new_tags.iter()
+            .filter(|tag| !template.tags.contains(tag))
+            .collect();
+        require!(
+            index_pages.len() == removed.len() + added.len(),
+            ErrorCode::IndexAccountsMismatch
+        );
+        
+        let (removed_pages, added_pages) = index_pages.split_at(removed.len());
+        for (tag, page) in removed.iter().zip(removed_pages.iter()) {
+            TemplateIndex::update_page(page, IndexKind::Tag, &TemplateIndex::tag_key(tag), |index| {
+                index.remove(&template_key)
+            })?;
+        }
+        for (tag, page) in added.iter().zip(added_pages.iter()) {
+            TemplateIndex::update_page(page, IndexKind::Tag, &TemplateIndex::tag_key(tag), |index| {
+                index.push(template_key)
+            })?;
+        }
+        
+        template.tags = new_tags;
+    }
+    
+    if let Some(delay) = update.update_delay {
+        template.update_delay = delay;
+    }
+    
+    if let Some(policy) = update.audit_policy {
+        template.audit_policy = policy;
+    }
+    
+    if let Some(maintainers) = update.maintainers {
+        template.maintainers = maintainers;
+    }
+    
+    if let Some(splits) = update.revenue_splits {
+        template.revenue_splits = splits;
+    }
+    
+    if let Some(pending_owner) = update.pending_owner {
+        template.pending_owner = pending_owner;
+    }
+    
+    if touches_listing {
+        let listing = listing.ok_or(ErrorCode::ListingRequired)?;
+        if let Some(pricing) = update.listing_pricing {
+            listing.pricing = pricing;
+        }
+        if let Some(active) = update.listing_active {
+            listing.is_active = active;
+        }
+        template.requires_license = listing.requires_license();
+        
+        emit!(ListingUpdatedEvent {
+            listing: listing.key(),
+            template: listing.template,
+            pricing: listing.pricing.clone(),
+            is_active: listing.is_active,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+    
+    template.updated_at = Clock::get()?.unix_timestamp;
+    
+    Ok(())
+}
+
+/// Pay template revenue out of `source`, following the template's split table
+///
+/// `shares` comes from `TemplateAccount::revenue_shares`; `accounts`
+/// starts with one token account per share, in the same order, each owned
+/// by the matching recipient. Any accounts after those are forwarded to
+/// the mint's transfer hook, see `transfer_cpi::transfer_checked`.
+pub fn pay_template_revenue<'info>(
+    shares: &[(Pubkey, u64)],
+    source: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    token_program: &Interface<'info, TokenInterface>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let recipients = accounts.get(..shares.len())
+        .ok_or(ErrorCode::RevenueAccountsMismatch)?;
+    
+    for ((recipient, share), destination) in shares.iter().zip(recipients.iter()) {
+        let destination_account: InterfaceAccount<'info, TokenAccount> =
+            InterfaceAccount::try_from(destination)?;
+        require!(destination_account.owner == *recipient, ErrorCode::RevenueAccountsMismatch);
+        require!(destination_account.mint == source.mint, ErrorCode::RevenueAccountsMismatch);
+        if *share == 0 {
+            continue;
+        }
+        
+        let cpi_accounts = token_interface::TransferChecked {
+            from: source.to_account_info(),
+            mint: mint.to_account_info(),
+            to: destination.clone(),
+            authority: authority.clone(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        )
+        .with_remaining_accounts(accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, *share, mint.decimals)?;
+    }
+    
+    Ok(())
+}
+
+/// What `vault` gained since it held `before`, i.e. a deposit net of any
+/// transfer fee
+fn received(vault: &mut InterfaceAccount<TokenAccount>, before: u64) -> Result<u64> {
+    vault.reload()?;
+    Ok(vault.amount.checked_sub(before).ok_or(ErrorCode::NumericOverflow)?)
+}
+
+/// Pay for a license out of the buyer's token account, split per the
+/// template's revenue table
+fn collect_license_payment<'info>(
+    ctx: &Context<'_, '_, '_, 'info, PurchaseLicense<'info>>,
+    amount: u64,
+) -> Result<()> {
+    pay_template_revenue(
+        &ctx.accounts.template.revenue_shares(amount),
+        &ctx.accounts.buyer_token_account,
+        &ctx.accounts.payment_mint,
+        &ctx.accounts.buyer.to_account_info(),
+        ctx.remaining_accounts,
+        &ctx.accounts.token_program,
+        &[],
+    )
+}
+
+fn record_license_sale(
+    listing: &mut Account<Listing>,
+    license: &mut Account<License>,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    license.purchased_at = now;
+    listing.sales_count = listing.sales_count.checked_add(1)
+        .ok_or(ErrorCode::NumericOverflow)?;
+    listing.revenue = listing.revenue.checked_add(amount)
+        .ok_or(ErrorCode::NumericOverflow)?;
+    
+    emit!(LicensePurchasedEvent {
+        listing: listing.key(),
+        template: listing.template,
+        holder: license.holder,
+        amount,
+        expires_at: license.expires_at,
+        executions_remaining: license.executions_remaining,
+        timestamp: now,
+    });
+    
+    Ok(())
+}
+
+/// Credit `amount` to `funder`'s deposit and the bounty's funded total
+fn record_bounty_deposit(
+    bounty: &mut Account<Bounty>,
+    deposit: &mut Account<BountyDeposit>,
+    funder: Pubkey,
+    deposit_bump: u8,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    if deposit.funder == Pubkey::default() {
+        deposit.bounty = bounty.key();
+        deposit.funder = funder;
+        deposit.bumps.deposit = deposit_bump;
+    }
+    deposit.amount = deposit.amount.checked_add(amount)
+        .ok_or(ErrorCode::NumericOverflow)?;
+    bounty.funded_total = bounty.funded_total.checked_add(amount)
+        .ok_or(ErrorCode::NumericOverflow)?;
+    
+    emit!(BountyFundedEvent {
+        bounty: bounty.key(),
+        funder: deposit.funder,
+        amount,
+        funded_total: bounty.funded_total,
+        timestamp: now,
+    });
+    
+    Ok(())
+}
+
+/// Count distinct, unexpired attestations from active auditors for
+/// `version` among `(attestation, auditor)` pairs in `accounts`
+fn count_valid_attestations<'info>(
+    version: &Account<'info, TemplateVersion>,
+    policy: &AuditPolicy,
+    accounts: &[AccountInfo<'info>],
+    now: i64,
+) -> Result<usize> {
+    require!(accounts.len().is_multiple_of(2), ErrorCode::AuditRequirementNotMet);
+    
+    let mut seen: Vec<Pubkey> = Vec::new();
+    for pair in accounts.chunks(2) {
+        let attestation: Account<'info, Attestation> = Account::try_from(&pair[0])?;
+        let auditor: Account<'info, Auditor> = Account::try_from(&pair[1])?;
+        
+        if attestation.template_version != version.key()
+            || attestation.auditor != auditor.authority
+            || !auditor.is_active
+            || attestation.expires_at <= now
+            || (policy.reject_critical && attestation.severity.critical > 0)
+            || seen.contains(&auditor.authority)
+        {
+            continue;
+        }
+        seen.push(auditor.authority);
+    }
+    
+    Ok(seen.len())
+}
+
+/// Integer square root, rounded down
+fn integer_sqrt(value: u64) -> u64 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+/// Read an unsigned LEB128 varint (at most 9 bytes, as multiformats allows)
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let byte = *bytes.get(i)?;
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *bytes = &bytes[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[account]
+#[derive(Default)]
+pub struct ExecutionAccount {
+    pub task_id: String,              // Unique task identifier
+    pub template: Pubkey,             // Template PDA account
+    pub beneficiary_user: Pubkey,     // User the task was executed for
+    pub payer: Pubkey,                // Wallet that paid the account's rent
+    pub executor: Pubkey,             // Key that recorded the execution
+    pub inputs_hash: String,          // Hash of input data (for verification)
+    pub outputs_hash: String,         // Hash of output data (for verification)
+    pub status: ExecutionStatus,      // Execution status
+    pub started_at: i64,              // Start timestamp
+    pub completed_at: i64,            // Completion timestamp
+    pub bumps: ExecutionBumps,        // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ExecutionBumps {
+    pub execution: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionStatus {
+    #[default]
+    InProgress,
+    Success,
+    Failure,
+}
+
+
+impl TemplateAccount {
+    pub const MAX_TAGS: usize = 5;
+    pub const MAX_TAG_LEN: usize = 20;
+    pub const MAX_MAINTAINERS: usize = 5;
+
+    pub fn space(template_id: &str) -> usize {
+        // Fixed size fields
+        let fixed_size = 8 + // Discriminator
+            32 + // Pubkey
+            32 + // Category
+            8 + // Created timestamp
+            8 + // Updated timestamp
+            8 + // Usage count
+            1 + // Is active
+            1 + 32 + // Pending owner
+            1 + // Requires license
+            8 + // Rating count
+            8 + // Rating weight total
+            8 + // Rating score total
+            1 + // Verified
+            AuditPolicy::space() + // Audit policy
+            8 + // Update delay
+            1; // Bump
+        
+        // Variable size fields
+        let variable_size = 4 + template_id.len() +      // template_id (String)
+            4 + 100 +                                    // template_name (allocate max)
+            4 + 20 +                                     // template_version (allocate max)
+            4 + Self::MAX_TAGS * (4 + Self::MAX_TAG_LEN) + // tags (allocate max)
+            ContentRef::max_space() +                    // metadata (allocate max)
+            4 + 32 * Self::MAX_MAINTAINERS +             // maintainers (allocate max)
+            4 + RevenueShare::space() * RevenueShare::MAX_SPLITS; // revenue_splits (allocate max)
+            
+        fixed_size + variable_size
+    }
+
+    /// Owners and maintainers may update template content
+    pub fn can_update(&self, signer: &Pubkey) -> bool {
+        self.creator == *signer || self.maintainers.contains(signer)
+    }
+
+    /// Split `amount` according to the revenue table
+    ///
+    /// Shares are rounded down and the rounding dust goes to the first
+    /// recipient, so the parts always add up to `amount`.
+    pub fn revenue_shares(&self, amount: u64) -> Vec<(Pubkey, u64)> {
+        if self.revenue_splits.is_empty() {
+            return vec![(self.creator, amount)];
+        }
+        
+        let mut shares: Vec<(Pubkey, u64)> = self.revenue_splits.iter()
+            .map(|split| {
+                let share = u128::from(amount) * u128::from(split.bps)
+                    / u128::from(RevenueShare::TOTAL_BPS);
+                (split.recipient, share as u64)
+            })
+            .collect();
+        let distributed: u64 = shares.iter().map(|(_, share)| share).sum();
+        shares[0].1 += amount - distributed;
+        shares
+    }
+
+    pub fn validate_maintainers(maintainers: &[Pubkey]) -> Result<()> {
+        require!(maintainers.len() <= Self::MAX_MAINTAINERS, ErrorCode::TooManyMaintainers);
+        for (i, maintainer) in maintainers.iter().enumerate() {
+            require!(!maintainers[..i].contains(maintainer), ErrorCode::DuplicateMaintainer);
+        }
+        Ok(())
+    }
+
+    pub fn validate_tags(tags: &[String]) -> Result<()> {
+        require!(tags.len() <= Self::MAX_TAGS, ErrorCode::TooManyTags);
+        for (i, tag) in tags.iter().enumerate() {
+            require!(!tag.is_empty() && tag.len() <= Self::MAX_TAG_LEN, ErrorCode::InvalidTag);
+            require!(
+                tag.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-'),
+                ErrorCode::InvalidTag
+            );
+            require!(!tags[..i].contains(tag), ErrorCode::DuplicateTag);
+        }
+        Ok(())
+    }
+}
+
+impl TemplateIndex {
+    pub const MAX_ENTRIES: usize = 32;
+
+    pub fn space() -> usize {
+        8 + // Discriminator
+            1 + // Kind
+            32 + // Key
+            4 + // Page
+            4 + 32 * Self::MAX_ENTRIES + // Templates (allocate max)
+            1 // Bump
+    }
+
+    pub fn tag_key(tag: &str) -> [u8; 32] {
+        anchor_lang::solana_program::hash::hash(tag.as_bytes()).to_bytes()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.templates.len() >= Self::MAX_ENTRIES
+    }
+
+    pub fn check(&self, kind: IndexKind, key: &[u8; 32]) -> Result<()> {
+        require!(self.kind == kind && self.key == *key, ErrorCode::IndexMismatch);
+        Ok(())
+    }
+
+    pub fn push(&mut self, template: Pubkey) -> Result<()> {
+        require!(!self.is_full(), ErrorCode::IndexPageFull);
+        self.templates.push(template);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, template: &Pubkey) -> Result<()> {
+        let position = self.templates.iter()
+            .position(|entry| entry == template)
+            .ok_or(ErrorCode::TemplateNotIndexed)?;
+        self.templates.swap_remove(position);
+        Ok(())
+    }
+
+    /// Load an index page from `remaining_accounts`, apply `f` and write it back
+    pub fn update_page<'info, F>(
+        info: &AccountInfo<'info>,
+        kind: IndexKind,
+        key: &[u8; 32],
+        f: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(&mut TemplateIndex) -> Result<()>,
+    {
+        require!(info.is_writable, ErrorCode::IndexMismatch);
+        let mut index: Account<'info, TemplateIndex> = Account::try_from(info)?;
+        index.check(kind, key)?;
+        f(&mut index)?;
+        index.exit(&crate::ID)
+    }
+}
+
+impl PendingTemplateUpdate {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Template
+            TemplateUpdate::space() + // Update
+            8 + // ETA
+            32 + // Queued by
+            8 + // Queued at
+            1 // Bump
+    }
+}
+
+impl Auditor {
+    pub const MAX_NAME_LEN: usize = 50;
+
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Authority
+            4 + Self::MAX_NAME_LEN + // Name (allocate max)
+            1 + // Is active
+            8 + // Attestation count
+            8 + // Registered at
+            1 // Bump
+    }
+}
+
+impl TemplateVersion {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Template
+            4 + 20 + // Version (allocate max)
+            32 + // Content digest
+            8 + // Attestation count
+            8 + // Published at
+            1 // Bump
+    }
+
+    /// The snapshot must match what the template currently points at
+    pub fn check_current(&self, template: &Account<TemplateAccount>) -> Result<()> {
+        require!(self.template == template.key(), ErrorCode::StaleTemplateVersion);
+        require!(self.version == template.template_version, ErrorCode::StaleTemplateVersion);
+        require!(
+            self.content_digest == template.metadata.digest(),
+            ErrorCode::StaleTemplateVersion
+        );
+        Ok(())
+    }
+}
+
+impl Attestation {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Auditor
+            32 + // Template version
+            32 + // Report hash
+            4 + // Severity summary
+            8 + // Expires at
+            8 + // Attested at
+            1 // Bump
+    }
+}
+
+impl Bounty {
+    pub const MAX_ARBITERS: usize = 3;
+
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Template
+            32 + // Vault
+            4 + 32 * Self::MAX_ARBITERS + // Arbiters (allocate max)
+            8 + // Funded total
+            8 + // Paid total
+            8 + // Refund pool
+            1 + // Refunds started
+            8 + // Report count
+            8 + // Expires at
+            8 + // Created at
+            2 // Bumps
+    }
+}
+
+impl BountyDeposit {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Bounty
+            32 + // Funder
+            8 + // Amount
+            1 + // Refunded
+            1 // Bump
+    }
+}
+
+impl BountyReport {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Bounty
+            32 + // Researcher
+            32 + // Commitment
+            1 + // Status
+            8 + // Payout
+            8 + // Submitted at
+            8 + // Resolved at
+            1 // Bump
+    }
 }
 
-#[account]
-#[derive(Default)]
-pub struct TemplateAccount {
-    pub template_id: String,          // Unique identifier
-    pub template_name: String,        // Display name
-    pub template_version: String,     // Version string
-    pub category: String,             // Category (defi, dao, analytics, etc.)
-    pub creator: Pubkey,              // Creator's public key
-    pub metadata: Vec<u8>,            // IPFS/Arweave CID or compressed metadata
-    pub created_at: i64,              // Creation timestamp
-    pub updated_at: i64,              // Last update timestamp
-    pub usage_count: u64,             // Number of executions
-    pub is_active: bool,              // Active status
-    pub bumps: TemplateBumps,         // PDA bumps
+impl Rating {
+    pub const MIN_SCORE: u8 = 1;
+    pub const MAX_SCORE: u8 = 5;
+
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Template
+            32 + // Rater
+            32 + // Execution
+            1 + // Score
+            8 + // Weight
+            1 + 32 + // Review hash
+            8 + // Rated at
+            1 // Bump
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
-pub struct TemplateBumps {
-    pub template: u8,
+impl RatingStake {
+    /// Seconds a rating keeps the rater's stake locked
+    pub const LOCK_PERIOD: i64 = 30 * 24 * 60 * 60;
+
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Owner
+            32 + // Vault
+            8 + // Amount
+            8 + // Locked until
+            2 // Bumps
+    }
 }
 
-#[account]
-#[derive(Default)]
-pub struct ExecutionAccount {
-    pub task_id: String,              // Unique task identifier
-    pub template: Pubkey,             // Template PDA account
-    pub user: Pubkey,                 // User who executed the task
-    pub inputs_hash: String,          // Hash of input data (for verification)
-    pub outputs_hash: String,         // Hash of output data (for verification)
-    pub status: ExecutionStatus,      // Execution status
-    pub started_at: i64,              // Start timestamp
-    pub completed_at: i64,            // Completion timestamp
-    pub bumps: ExecutionBumps,        // PDA bumps
+impl Listing {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Template
+            32 + // Payment mint
+            PricingModel::space() + // Pricing
+            1 + // Is active
+            8 + // Sales count
+            8 + // Revenue
+            8 + // Created timestamp
+            1 // Bump
+    }
+
+    /// Only free listings leave executions ungated; pausing a paid listing
+    /// stops new sales but existing licenses are still required
+    pub fn requires_license(&self) -> bool {
+        self.pricing != PricingModel::Free
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
-pub struct ExecutionBumps {
-    pub execution: u8,
+impl License {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Listing
+            32 + // Template
+            32 + // Holder
+            1 + // Perpetual
+            8 + // Expires at
+            8 + // Executions remaining
+            8 + // Purchased at
+            1 // Bump
+    }
+
+    fn init_if_new(&mut self, listing: &Account<Listing>, holder: Pubkey, bump: u8) {
+        if self.holder == Pubkey::default() {
+            self.listing = listing.key();
+            self.template = listing.template;
+            self.holder = holder;
+            self.bumps.license = bump;
+        }
+    }
+
+    /// Check the license covers one more execution at `now`, drawing down
+    /// prepaid uses only when nothing else covers it
+    pub fn consume(&mut self, now: i64) -> Result<()> {
+        if self.perpetual || self.expires_at > now {
+            return Ok(());
+        }
+        require!(self.executions_remaining > 0, ErrorCode::LicenseExpired);
+        self.executions_remaining -= 1;
+        Ok(())
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum ExecutionStatus {
-    InProgress,
-    Success,
-    Failure,
+impl CreditAccount {
+    pub const MAX_SPENDERS: usize = 5;
+    pub const SECONDS_PER_DAY: i64 = 86_400;
+
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Owner
+            32 + // Mint
+            32 + // Vault
+            8 + // Total deposited
+            8 + // Total spent
+            4 + CreditSpender::space() * Self::MAX_SPENDERS + // Spenders (allocate max)
+            8 + // Created at
+            2 // Bumps
+    }
+
+    /// Account for `amount` drawn by `spender`, who must be the owner or a
+    /// delegated spender still under today's limit
+    pub fn spend(&mut self, spender: &Pubkey, amount: u64, now: i64) -> Result<()> {
+        if *spender != self.owner {
+            let day = now / Self::SECONDS_PER_DAY;
+            let delegate = self.spenders.iter_mut()
+                .find(|s| s.spender == *spender)
+                .ok_or(ErrorCode::UnauthorizedCreditSpender)?;
+            if delegate.day != day {
+                delegate.day = day;
+                delegate.spent_today = 0;
+            }
+            let spent = delegate.spent_today.checked_add(amount)
+                .ok_or(ErrorCode::NumericOverflow)?;
+            require!(spent <= delegate.daily_limit, ErrorCode::CreditLimitExceeded);
+            delegate.spent_today = spent;
+        }
+        
+        self.total_spent = self.total_spent.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        Ok(())
+    }
 }
 
-impl Default for ExecutionStatus {
-    fn default() -> Self {
-        ExecutionStatus::InProgress
+impl SessionKey {
+    pub const MAX_SCOPE: usize = 8;
+    pub const MAX_DURATION: i64 = 30 * 86_400;
+
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // User
+            32 + // Session key
+            4 + 32 * Self::MAX_SCOPE + // Scope (allocate max)
+            8 + // Expires at
+            8 + // Max spend
+            8 + // Spent
+            8 + // Created at
+            1 // Bump
+    }
+
+    /// Check the session is live and covers `template`
+    pub fn check(&self, template: &Pubkey, now: i64) -> Result<()> {
+        require!(now < self.expires_at, ErrorCode::SessionExpired);
+        require!(self.scope.contains(template), ErrorCode::TemplateOutOfSessionScope);
+        Ok(())
+    }
+
+    /// Account for credit drawn through the session
+    pub fn spend(&mut self, amount: u64) -> Result<()> {
+        let spent = self.spent.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        require!(spent <= self.max_spend, ErrorCode::SessionSpendLimitExceeded);
+        self.spent = spent;
+        Ok(())
     }
 }
 
-impl TemplateAccount {
-    pub fn space(template_id: &str) -> usize {
+impl CreditSpender {
+    pub fn space() -> usize {
+        32 + // Spender
+            8 + // Daily limit
+            8 + // Day
+            8 // Spent today
+    }
+}
+
+impl ProtocolConfig {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Admin
+            32 + // Curator
+            32 + // Guardian
+            32 + // WALE mint
+            8 + // Min rating stake
+            8 + // Category count
+            1 // Bump
+    }
+}
+
+impl Category {
+    pub const MAX_SLUG_LEN: usize = 20;
+    pub const MAX_DEPTH: usize = 4;
+    pub const MAX_DISPLAY_NAME_LEN: usize = 50;
+
+    pub fn space() -> usize {
         // Fixed size fields
         let fixed_size = 8 + // Discriminator
-            32 + // Pubkey
-            8 + // Created timestamp
-            8 + // Updated timestamp
-            8 + // Usage count
+            1 + 32 + // Parent (Option<Pubkey>)
             1 + // Is active
-            1 + // Bump
-            4; // Vec header for metadata
+            8 + // Template count
+            8 + // Created timestamp
+            1; // Bump
         
         // Variable size fields
-        let variable_size = 4 + template_id.len() +      // template_id (String)
-            4 + 100 +                                    // template_name (allocate max)
-            4 + 20 +                                     // template_version (allocate max)
-            4 + 20 +                                     // category (allocate max)
-            1024;                                        // metadata (allocate max)
+        let variable_size = 4 + Self::MAX_SLUG_LEN +   // slug (allocate max)
+            4 + Self::MAX_DISPLAY_NAME_LEN;            // display_name (allocate max)
             
         fixed_size + variable_size
     }
+
+    /// Slugs are the canonical key, so only `[a-z0-9-]` is accepted and
+    /// "DeFi" or "de_fi" can't be registered next to "defi".
+    pub fn validate_slug(slug: &str) -> Result<()> {
+        require!(!slug.is_empty(), ErrorCode::InvalidCategorySlug);
+        require!(slug.len() <= Self::MAX_SLUG_LEN, ErrorCode::CategoryTooLong);
+        require!(
+            slug.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-'),
+            ErrorCode::InvalidCategorySlug
+        );
+        require!(
+            !slug.starts_with('-') && !slug.ends_with('-'),
+            ErrorCode::InvalidCategorySlug
+        );
+        Ok(())
+    }
 }
 
 impl ExecutionAccount {
+    pub const CONSENT_DOMAIN: &'static [u8] = b"mcp:execution-consent:v1";
+
+    /// Message a beneficiary signs to let `executor` record one execution
+    /// on their behalf
+    ///
+    /// It is bound to a single task id, so the consent is used up once the
+    /// execution PDA exists. When the execution is paid from credits,
+    /// `charge` is the credit account and price, so a repriced listing or a
+    /// different account needs a fresh signature.
+    pub fn consent_message(
+        beneficiary: &Pubkey,
+        executor: &Pubkey,
+        template: &Pubkey,
+        task_id: &str,
+        inputs_hash: &str,
+        charge: Option<&(Pubkey, u64)>,
+    ) -> Vec<u8> {
+        let (credit, price) = charge.copied().unwrap_or_default();
+        [
+            Self::CONSENT_DOMAIN,
+            crate::ID.as_ref(),
+            beneficiary.as_ref(),
+            executor.as_ref(),
+            template.as_ref(),
+            &id_seed(task_id),
+            credit.as_ref(),
+            &price.to_le_bytes(),
+            inputs_hash.as_bytes(),
+        ]
+        .concat()
+    }
+
     pub fn space(task_id: &str) -> usize {
         // Fixed size fields
         let fixed_size = 8 + // Discriminator
             32 + // Template PDA
-            32 + // User pubkey
+            32 + // Beneficiary user
+            32 + // Payer
+            32 + // Executor
             4 + // Status (enum)
             8 + // Started timestamp
             8 + // Completed timestamp
@@ -356,6 +4392,152 @@ pub enum ErrorCode {
     MetadataTooLarge,
     #[msg("Invalid execution status")]
     InvalidExecutionStatus,
+    #[msg("Invalid IPFS CID, expected a raw-codec CIDv1 with a sha2-256 multihash")]
+    InvalidCid,
+    #[msg("Invalid Arweave transaction id")]
+    InvalidArweaveTxId,
+    #[msg("Invalid content URL, expected https")]
+    InvalidContentUrl,
+    #[msg("Numeric overflow occurred")]
+    NumericOverflow,
+    #[msg("Signer is not the protocol admin")]
+    UnauthorizedAdmin,
+    #[msg("Category slug must be lowercase letters, digits or dashes")]
+    InvalidCategorySlug,
+    #[msg("Category is not active")]
+    CategoryInactive,
+    #[msg("Category cannot be its own parent or ancestor")]
+    InvalidParentCategory,
+    #[msg("Too many tags")]
+    TooManyTags,
+    #[msg("Tags must be lowercase letters, digits or dashes")]
+    InvalidTag,
+    #[msg("Duplicate tag")]
+    DuplicateTag,
+    #[msg("Index page does not match the expected category, tag or creator")]
+    IndexMismatch,
+    #[msg("Wrong number of index accounts supplied")]
+    IndexAccountsMismatch,
+    #[msg("Index page is full")]
+    IndexPageFull,
+    #[msg("Index pages must be opened in order")]
+    IndexPageOutOfOrder,
+    #[msg("Template is not listed on this index page")]
+    TemplateNotIndexed,
+    #[msg("Signer is not the template owner")]
+    UnauthorizedTemplateOwner,
+    #[msg("Signer is neither the template owner nor a maintainer")]
+    UnauthorizedTemplateUpdate,
+    #[msg("Signer is not the pending template owner")]
+    NotPendingOwner,
+    #[msg("Too many maintainers")]
+    TooManyMaintainers,
+    #[msg("Duplicate maintainer")]
+    DuplicateMaintainer,
+    #[msg("Too many revenue splits")]
+    TooManyRevenueSplits,
+    #[msg("Revenue splits must be unique, non-zero and sum to 10000 bps")]
+    InvalidRevenueSplits,
+    #[msg("Revenue recipient accounts do not match the split table")]
+    RevenueAccountsMismatch,
+    #[msg("Paid prices and subscription periods must be positive")]
+    InvalidPricing,
+    #[msg("Listing is not active")]
+    ListingInactive,
+    #[msg("Instruction does not match the listing's pricing model")]
+    WrongPricingModel,
+    #[msg("Quantity must be positive")]
+    InvalidQuantity,
+    #[msg("Payment must be made in the listing's mint")]
+    InvalidPaymentMint,
+    #[msg("A perpetual license is already owned")]
+    LicenseAlreadyOwned,
+    #[msg("This template requires a license")]
+    LicenseRequired,
+    #[msg("License does not belong to this template and caller")]
+    InvalidLicense,
+    #[msg("License has expired or has no executions left")]
+    LicenseExpired,
+    #[msg("Rating score must be between 1 and 5")]
+    InvalidRatingScore,
+    #[msg("Rating requires a successful execution of this template by the rater")]
+    RatingNotEarned,
+    #[msg("Locked WALE is below the minimum rating stake")]
+    InsufficientRatingStake,
+    #[msg("Signer is not the curator")]
+    UnauthorizedCurator,
+    #[msg("Auditor is not active")]
+    AuditorInactive,
+    #[msg("Attestation expiry must be in the future")]
+    InvalidAttestationExpiry,
+    #[msg("Audit policy requires too many attestations")]
+    InvalidAuditPolicy,
+    #[msg("Template version does not match the template's current content")]
+    StaleTemplateVersion,
+    #[msg("Not enough valid audit attestations for this template")]
+    AuditRequirementNotMet,
+    #[msg("Bounty expiry must be in the future")]
+    InvalidBountyExpiry,
+    #[msg("Too many bounty arbiters")]
+    TooManyArbiters,
+    #[msg("Only the template creator can fund a bounty from a wallet")]
+    UnauthorizedBountyFunder,
+    #[msg("Only an arbiter independent of the template creator can resolve reports")]
+    UnauthorizedBountyResolver,
+    #[msg("Bounty has expired")]
+    BountyExpired,
+    #[msg("Bounty has not expired yet")]
+    BountyNotExpired,
+    #[msg("Bounty deposit was already refunded")]
+    BountyAlreadyRefunded,
+    #[msg("Report has already been resolved")]
+    ReportAlreadyResolved,
+    #[msg("Payout exceeds the bounty vault balance")]
+    InsufficientBountyFunds,
+    #[msg("Payout account must be a token account owned by the researcher")]
+    InvalidBountyPayoutAccount,
+    #[msg("This change must be queued through the update timelock")]
+    TimelockRequired,
+    #[msg("Update delay must be between 0 and 30 days")]
+    InvalidTimelockDelay,
+    #[msg("Timelock has not elapsed")]
+    TimelockNotElapsed,
+    #[msg("Signer is neither the guardian nor the template owner")]
+    UnauthorizedGuardian,
+    #[msg("Credit vault or token program does not match the credit account")]
+    InvalidCreditAccount,
+    #[msg("Credit balance is too low")]
+    InsufficientCredit,
+    #[msg("Signer may not spend from this credit account")]
+    UnauthorizedCreditSpender,
+    #[msg("Spender's daily credit limit exceeded")]
+    CreditLimitExceeded,
+    #[msg("Too many delegated credit spenders")]
+    TooManyCreditSpenders,
+    #[msg("Signer is neither the execution's user nor a session key for it")]
+    UnauthorizedExecutionUpdate,
+    #[msg("Session scope must list between 1 and 8 templates")]
+    InvalidSessionScope,
+    #[msg("Session expiry must be in the future and at most 30 days away")]
+    InvalidSessionExpiry,
+    #[msg("Session key has expired")]
+    SessionExpired,
+    #[msg("Template is outside the session's scope")]
+    TemplateOutOfSessionScope,
+    #[msg("Session spend limit exceeded")]
+    SessionSpendLimitExceeded,
+    #[msg("Beneficiary has not consented to this execution")]
+    MissingUserConsent,
+    #[msg("Category nesting is too deep")]
+    CategoryTooDeep,
+    #[msg("Ancestor accounts do not match the parent category chain")]
+    CategoryAncestorsMismatch,
+    #[msg("Rating stake is locked by a recent rating")]
+    RatingStakeLocked,
+    #[msg("Bounties need at least one arbiter other than the template creator")]
+    BountyArbiterRequired,
+    #[msg("Update changes the listing but no listing account was passed")]
+    ListingRequired,
 }
 
 // Events
@@ -378,6 +4560,7 @@ pub struct ExecutionRecordedEvent {
     pub task_id: String,
     pub template_id: String,
     pub user: Pubkey,
+    pub executor: Pubkey,
     pub status: ExecutionStatus,
     pub timestamp: i64,
 }
@@ -387,4 +4570,378 @@ pub struct ExecutionUpdatedEvent {
     pub task_id: String,
     pub status: ExecutionStatus,
     pub timestamp: i64,
-} 
\ No newline at end of file
+} 
+
+#[event]
+pub struct ConfigUpdatedEvent {
+    pub admin: Pubkey,
+    pub curator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CategoryCreatedEvent {
+    pub category: Pubkey,
+    pub slug: String,
+    pub parent: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CategoryUpdatedEvent {
+    pub category: Pubkey,
+    pub slug: String,
+    pub is_active: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TemplateOwnershipTransferStartedEvent {
+    pub template_id: String,
+    pub owner: Pubkey,
+    pub pending_owner: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TemplateOwnershipTransferredEvent {
+    pub template_id: String,
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TemplateMaintainersUpdatedEvent {
+    pub template_id: String,
+    pub maintainers: Vec<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RevenueSplitsUpdatedEvent {
+    pub template_id: String,
+    pub splits: Vec<RevenueShare>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingUpdatedEvent {
+    pub listing: Pubkey,
+    pub template: Pubkey,
+    pub pricing: PricingModel,
+    pub is_active: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LicensePurchasedEvent {
+    pub listing: Pubkey,
+    pub template: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub expires_at: i64,
+    pub executions_remaining: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TemplateRatedEvent {
+    pub template: Pubkey,
+    pub rater: Pubkey,
+    pub score: u8,
+    pub weight: u64,
+    pub review_hash: Option<[u8; 32]>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RatingStakeChangedEvent {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub locked_until: i64,
+}
+
+#[event]
+pub struct TemplateVerificationChangedEvent {
+    pub template: Pubkey,
+    pub template_id: String,
+    pub curator: Pubkey,
+    pub verified: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuditorUpdatedEvent {
+    pub auditor: Pubkey,
+    pub name: String,
+    pub is_active: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TemplateVersionPublishedEvent {
+    pub template: Pubkey,
+    pub template_version: Pubkey,
+    pub version: String,
+    pub content_digest: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TemplateVersionAttestedEvent {
+    pub template: Pubkey,
+    pub template_version: Pubkey,
+    pub auditor: Pubkey,
+    pub report_hash: [u8; 32],
+    pub severity: SeveritySummary,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuditPolicyUpdatedEvent {
+    pub template: Pubkey,
+    pub policy: AuditPolicy,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BountyCreatedEvent {
+    pub bounty: Pubkey,
+    pub template: Pubkey,
+    pub arbiters: Vec<Pubkey>,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BountyFundedEvent {
+    pub bounty: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub funded_total: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BountyReportSubmittedEvent {
+    pub bounty: Pubkey,
+    pub report: Pubkey,
+    pub researcher: Pubkey,
+    pub commitment: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BountyReportResolvedEvent {
+    pub bounty: Pubkey,
+    pub report: Pubkey,
+    pub researcher: Pubkey,
+    pub resolver: Pubkey,
+    pub status: ReportStatus,
+    pub payout: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BountyRefundedEvent {
+    pub bounty: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TemplateUpdateDelayChangedEvent {
+    pub template: Pubkey,
+    pub delay: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TemplateUpdateQueuedEvent {
+    pub template: Pubkey,
+    pub queued_by: Pubkey,
+    pub update: TemplateUpdate,
+    pub eta: i64,
+}
+
+#[event]
+pub struct TemplateUpdateCancelledEvent {
+    pub template: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TemplateUpdateExecutedEvent {
+    pub template: Pubkey,
+    pub queued_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CreditToppedUpEvent {
+    pub credit: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CreditWithdrawnEvent {
+    pub credit: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CreditSpentEvent {
+    pub credit: Pubkey,
+    pub spender: Pubkey,
+    pub template: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CreditSpenderUpdatedEvent {
+    pub credit: Pubkey,
+    pub spender: Pubkey,
+    pub daily_limit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SessionKeyCreatedEvent {
+    pub user: Pubkey,
+    pub session_key: Pubkey,
+    pub scope: Vec<Pubkey>,
+    pub expires_at: i64,
+    pub max_spend: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SessionKeyRevokedEvent {
+    pub user: Pubkey,
+    pub session_key: Pubkey,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw_cid(digest: [u8; 32]) -> Vec<u8> {
+        let mut cid = vec![0x01, 0x55, 0x12, 0x20];
+        cid.extend_from_slice(&digest);
+        cid
+    }
+
+    #[test]
+    fn read_varint_decodes_single_and_multi_byte_values() {
+        let mut bytes: &[u8] = &[0x05, 0xff];
+        assert_eq!(read_varint(&mut bytes), Some(5));
+        assert_eq!(bytes, &[0xff]);
+
+        let mut bytes: &[u8] = &[0x80, 0x01];
+        assert_eq!(read_varint(&mut bytes), Some(128));
+        assert!(bytes.is_empty());
+
+        let mut bytes: &[u8] = &[0xac, 0x02, 0x07];
+        assert_eq!(read_varint(&mut bytes), Some(300));
+        assert_eq!(bytes, &[0x07]);
+    }
+
+    #[test]
+    fn read_varint_rejects_truncated_and_overlong_input() {
+        let mut bytes: &[u8] = &[];
+        assert_eq!(read_varint(&mut bytes), None);
+
+        let mut bytes: &[u8] = &[0x80, 0x80];
+        assert_eq!(read_varint(&mut bytes), None);
+
+        let mut bytes: &[u8] = &[0xff; 10];
+        assert_eq!(read_varint(&mut bytes), None);
+        assert_eq!(bytes.len(), 10);
+    }
+
+    #[test]
+    fn cid_sha256_accepts_raw_sha256_cids() {
+        let digest = [7u8; 32];
+        assert_eq!(ContentRef::cid_sha256(&raw_cid(digest)), Some(digest));
+    }
+
+    #[test]
+    fn cid_sha256_rejects_other_codecs_versions_and_hashes() {
+        let mut dag_pb = raw_cid([1u8; 32]);
+        dag_pb[1] = 0x70;
+        assert_eq!(ContentRef::cid_sha256(&dag_pb), None);
+
+        let mut v0 = raw_cid([1u8; 32]);
+        v0[0] = 0x00;
+        assert_eq!(ContentRef::cid_sha256(&v0), None);
+
+        let mut blake3 = raw_cid([1u8; 32]);
+        blake3[2] = 0x1e;
+        assert_eq!(ContentRef::cid_sha256(&blake3), None);
+
+        let mut short = raw_cid([1u8; 32]);
+        short.pop();
+        assert_eq!(ContentRef::cid_sha256(&short), None);
+
+        let mut wrong_len = raw_cid([1u8; 32]);
+        wrong_len[3] = 0x1f;
+        assert_eq!(ContentRef::cid_sha256(&wrong_len), None);
+    }
+
+    #[test]
+    fn validate_checks_ipfs_cids() {
+        let digest = [9u8; 32];
+        let valid = ContentRef::Ipfs { cid: raw_cid(digest) };
+        assert!(valid.validate().is_ok());
+        assert_eq!(valid.sha256(), Some(digest));
+        assert_eq!(valid.digest(), digest);
+
+        let mut dag_pb = raw_cid(digest);
+        dag_pb[1] = 0x70;
+        assert!(ContentRef::Ipfs { cid: dag_pb }.validate().is_err());
+
+        let mut oversized = raw_cid(digest);
+        oversized.resize(ContentRef::MAX_CID_LEN + 1, 0);
+        assert!(ContentRef::Ipfs { cid: oversized }.validate().is_err());
+    }
+
+    #[test]
+    fn queued_updates_need_the_queuer_to_keep_update_rights() {
+        let owner = Pubkey::new_unique();
+        let maintainer = Pubkey::new_unique();
+        let mut template = TemplateAccount {
+            creator: owner,
+            maintainers: vec![maintainer],
+            ..Default::default()
+        };
+        let content = TemplateUpdate {
+            template_name: Some("renamed".to_string()),
+            ..Default::default()
+        };
+        let splits = TemplateUpdate {
+            revenue_splits: Some(Vec::new()),
+            ..Default::default()
+        };
+        
+        assert!(content.authorize(&template, &maintainer).is_ok());
+        assert!(splits.authorize(&template, &maintainer).is_err());
+        assert!(splits.authorize(&template, &owner).is_ok());
+        
+        // Ownership moves on and the maintainer is dropped
+        template.creator = Pubkey::new_unique();
+        template.maintainers.clear();
+        assert!(content.authorize(&template, &maintainer).is_err());
+        assert!(splits.authorize(&template, &owner).is_err());
+        assert!(content.authorize(&template, &owner).is_err());
+    }
+}
@@ -0,0 +1,571 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+declare_id!("WALEMsig11111111111111111111111111111111111");
+
+#[program]
+pub mod multisig {
+    use super::*;
+    
+    /// Create an M-of-N multisig
+    ///
+    /// The multisig's `signer` PDA is the key to install as
+    /// `TokenInfo.authority` in `wale_token` or `ProtocolConfig.admin` in
+    /// `mcp`. It signs the instructions of every executed transaction.
+    pub fn create_multisig(
+        ctx: Context<CreateMultisig>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        Multisig::validate_members(&members, threshold)?;
+        
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.create_key = ctx.accounts.create_key.key();
+        multisig.members = members;
+        multisig.threshold = threshold;
+        multisig.transaction_count = 0;
+        multisig.member_set_seqno = 0;
+        multisig.bumps.multisig = *ctx.bumps.get("multisig").unwrap();
+        multisig.bumps.signer = *ctx.bumps.get("signer").unwrap();
+        
+        emit!(MembersChangedEvent {
+            multisig: multisig.key(),
+            signer: ctx.accounts.signer.key(),
+            members: multisig.members.clone(),
+            threshold,
+            member_set_seqno: multisig.member_set_seqno,
+        });
+        
+        Ok(())
+    }
+    
+    /// Replace the member set and threshold
+    ///
+    /// Only the multisig's own signer PDA can call this, so changes go
+    /// through an approved transaction. Transactions created under the
+    /// previous member set can no longer execute.
+    pub fn set_members(
+        ctx: Context<SetMembers>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        Multisig::validate_members(&members, threshold)?;
+        
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.members = members;
+        multisig.threshold = threshold;
+        multisig.member_set_seqno = multisig.member_set_seqno.checked_add(1)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(MembersChangedEvent {
+            multisig: multisig.key(),
+            signer: ctx.accounts.signer.key(),
+            members: multisig.members.clone(),
+            threshold,
+            member_set_seqno: multisig.member_set_seqno,
+        });
+        
+        Ok(())
+    }
+    
+    /// Propose a transaction; the proposer's approval is recorded
+    pub fn create_transaction(
+        ctx: Context<CreateTransaction>,
+        instructions: Vec<MultisigInstruction>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let multisig = &mut ctx.accounts.multisig;
+        let transaction = &mut ctx.accounts.transaction;
+        
+        let index = multisig.member_index(&ctx.accounts.proposer.key())?;
+        MultisigInstruction::validate(&instructions)?;
+        
+        transaction.multisig = multisig.key();
+        transaction.id = multisig.transaction_count;
+        transaction.proposer = ctx.accounts.proposer.key();
+        transaction.instructions = instructions;
+        transaction.approvals = 1 << index;
+        transaction.member_set_seqno = multisig.member_set_seqno;
+        transaction.status = TransactionStatus::Active;
+        transaction.created_at = now;
+        transaction.executed_at = 0;
+        transaction.bumps.transaction = *ctx.bumps.get("transaction").unwrap();
+        
+        multisig.transaction_count = multisig.transaction_count.checked_add(1)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(TransactionCreatedEvent {
+            multisig: multisig.key(),
+            transaction: transaction.key(),
+            id: transaction.id,
+            proposer: transaction.proposer,
+            instruction_count: transaction.instructions.len() as u8,
+        });
+        
+        Ok(())
+    }
+    
+    /// Approve an active transaction
+    pub fn approve(
+        ctx: Context<Approve>,
+    ) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let transaction = &mut ctx.accounts.transaction;
+        
+        transaction.check_active(multisig)?;
+        let bit = 1 << multisig.member_index(&ctx.accounts.member.key())?;
+        require!(transaction.approvals & bit == 0, ErrorCode::AlreadyApproved);
+        
+        transaction.approvals |= bit;
+        
+        emit!(ApprovalChangedEvent {
+            transaction: transaction.key(),
+            member: ctx.accounts.member.key(),
+            approved: true,
+            approval_count: transaction.approval_count(),
+        });
+        
+        Ok(())
+    }
+    
+    /// Withdraw an approval from an active transaction
+    pub fn revoke_approval(
+        ctx: Context<Approve>,
+    ) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let transaction = &mut ctx.accounts.transaction;
+        
+        transaction.check_active(multisig)?;
+        let bit = 1 << multisig.member_index(&ctx.accounts.member.key())?;
+        require!(transaction.approvals & bit != 0, ErrorCode::NotApproved);
+        
+        transaction.approvals &= !bit;
+        
+        emit!(ApprovalChangedEvent {
+            transaction: transaction.key(),
+            member: ctx.accounts.member.key(),
+            approved: false,
+            approval_count: transaction.approval_count(),
+        });
+        
+        Ok(())
+    }
+    
+    /// Execute a transaction that has reached the threshold
+    ///
+    /// `remaining_accounts` must contain every account referenced by the
+    /// transaction's instructions, including the target programs. The
+    /// multisig signer PDA signs each CPI.
+    pub fn execute_transaction<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteTransaction<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let multisig = &ctx.accounts.multisig;
+        let transaction = &mut ctx.accounts.transaction;
+        
+        transaction.check_active(multisig)?;
+        multisig.member_index(&ctx.accounts.member.key())?;
+        require!(
+            transaction.approval_count() >= multisig.threshold,
+            ErrorCode::ThresholdNotMet
+        );
+        
+        let multisig_key = multisig.key();
+        let seeds = &[b"signer".as_ref(), multisig_key.as_ref(), &[multisig.bumps.signer]];
+        let signer = &[&seeds[..]];
+        
+        // Transactions may call this program, so mark the transaction
+        // executed and persist that before any CPI can re-enter with it
+        transaction.status = TransactionStatus::Executed;
+        transaction.executed_at = now;
+        transaction.exit(&crate::ID)?;
+        
+        for ix in transaction.instructions.iter() {
+            let instruction = Instruction {
+                program_id: ix.program_id,
+                accounts: ix.accounts.iter()
+                    .map(|meta| AccountMeta {
+                        pubkey: meta.pubkey,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect(),
+                data: ix.data.clone(),
+            };
+            invoke_signed(&instruction, ctx.remaining_accounts, signer)?;
+        }
+        
+        emit!(TransactionExecutedEvent {
+            transaction: transaction.key(),
+            executed_by: ctx.accounts.member.key(),
+            approval_count: transaction.approval_count(),
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+    
+    /// Cancel an active transaction; only the proposer may cancel
+    pub fn cancel_transaction(
+        ctx: Context<CancelTransaction>,
+    ) -> Result<()> {
+        let transaction = &mut ctx.accounts.transaction;
+        
+        require!(transaction.status == TransactionStatus::Active, ErrorCode::TransactionNotActive);
+        
+        transaction.status = TransactionStatus::Cancelled;
+        
+        emit!(TransactionCancelledEvent {
+            transaction: transaction.key(),
+            cancelled_by: ctx.accounts.proposer.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreateMultisig<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// Unique key the multisig address is derived from
+    pub create_key: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = Multisig::space(),
+        seeds = [b"multisig", create_key.key().as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    /// CHECK: PDA that signs executed transactions, holds no data
+    #[account(
+        seeds = [b"signer", multisig.key().as_ref()],
+        bump
+    )]
+    pub signer: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetMembers<'info> {
+    #[account(
+        seeds = [b"signer", multisig.key().as_ref()],
+        bump = multisig.bumps.signer,
+    )]
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"multisig", multisig.create_key.as_ref()],
+        bump = multisig.bumps.multisig,
+    )]
+    pub multisig: Account<'info, Multisig>,
+}
+
+#[derive(Accounts)]
+pub struct CreateTransaction<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"multisig", multisig.create_key.as_ref()],
+        bump = multisig.bumps.multisig,
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = Transaction::space(),
+        seeds = [b"transaction", multisig.key().as_ref(), &multisig.transaction_count.to_le_bytes()],
+        bump
+    )]
+    pub transaction: Account<'info, Transaction>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Approve<'info> {
+    pub member: Signer<'info>,
+    
+    #[account(
+        seeds = [b"multisig", multisig.create_key.as_ref()],
+        bump = multisig.bumps.multisig,
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    #[account(
+        mut,
+        seeds = [b"transaction", multisig.key().as_ref(), &transaction.id.to_le_bytes()],
+        bump = transaction.bumps.transaction,
+    )]
+    pub transaction: Account<'info, Transaction>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTransaction<'info> {
+    pub member: Signer<'info>,
+    
+    #[account(
+        seeds = [b"multisig", multisig.create_key.as_ref()],
+        bump = multisig.bumps.multisig,
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    #[account(
+        mut,
+        seeds = [b"transaction", multisig.key().as_ref(), &transaction.id.to_le_bytes()],
+        bump = transaction.bumps.transaction,
+    )]
+    pub transaction: Account<'info, Transaction>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTransaction<'info> {
+    pub proposer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"transaction", transaction.multisig.as_ref(), &transaction.id.to_le_bytes()],
+        bump = transaction.bumps.transaction,
+        constraint = transaction.proposer == proposer.key() @ ErrorCode::UnauthorizedProposer,
+    )]
+    pub transaction: Account<'info, Transaction>,
+}
+
+#[account]
+#[derive(Default)]
+pub struct Multisig {
+    pub create_key: Pubkey,           // Key the multisig address derives from
+    pub members: Vec<Pubkey>,         // Members allowed to propose and approve
+    pub threshold: u8,                // Approvals needed to execute
+    pub transaction_count: u64,       // Number of transactions created
+    pub member_set_seqno: u32,        // Bumped whenever the member set changes
+    pub bumps: MultisigBumps,         // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct MultisigBumps {
+    pub multisig: u8,
+    pub signer: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransactionStatus {
+    #[default]
+    Active,
+    Executed,
+    Cancelled,
+}
+
+
+/// Serialized account meta for a transaction instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct MultisigAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// An instruction a transaction runs when executed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct MultisigInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<MultisigAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl MultisigInstruction {
+    pub const MAX_INSTRUCTIONS: usize = 4;
+    pub const MAX_ACCOUNTS: usize = 8;
+    pub const MAX_DATA_LEN: usize = 256;
+    
+    pub fn space() -> usize {
+        32 + // Program id
+            4 + Self::MAX_ACCOUNTS * (32 + 1 + 1) + // Accounts (allocate max)
+            4 + Self::MAX_DATA_LEN // Data (allocate max)
+    }
+    
+    /// Transactions may only call the admin surface of the WALE programs
+    pub fn validate(instructions: &[MultisigInstruction]) -> Result<()> {
+        require!(!instructions.is_empty(), ErrorCode::InvalidTransactionInstructions);
+        require!(
+            instructions.len() <= Self::MAX_INSTRUCTIONS,
+            ErrorCode::InvalidTransactionInstructions
+        );
+        for ix in instructions {
+            require!(
+                ix.program_id == mcp::ID || ix.program_id == wale_token::ID || ix.program_id == crate::ID,
+                ErrorCode::UnsupportedTransactionTarget
+            );
+            require!(ix.accounts.len() <= Self::MAX_ACCOUNTS, ErrorCode::InvalidTransactionInstructions);
+            require!(ix.data.len() <= Self::MAX_DATA_LEN, ErrorCode::InvalidTransactionInstructions);
+        }
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct Transaction {
+    pub multisig: Pubkey,             // Multisig the transaction belongs to
+    pub id: u64,                      // Sequential transaction id
+    pub proposer: Pubkey,             // Member who proposed it
+    pub instructions: Vec<MultisigInstruction>, // Instructions run on execution
+    pub approvals: u16,               // Bitmask of approving member indexes
+    pub member_set_seqno: u32,        // Member set the approvals refer to
+    pub status: TransactionStatus,    // Lifecycle state
+    pub created_at: i64,              // Creation timestamp
+    pub executed_at: i64,             // Execution timestamp
+    pub bumps: TransactionBumps,      // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct TransactionBumps {
+    pub transaction: u8,
+}
+
+impl Multisig {
+    pub const MAX_MEMBERS: usize = 10;
+    
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Create key
+            4 + Self::MAX_MEMBERS * 32 + // Members (allocate max)
+            1 + // Threshold
+            8 + // Transaction count
+            4 + // Member set seqno
+            2 // Bumps
+    }
+    
+    pub fn validate_members(members: &[Pubkey], threshold: u8) -> Result<()> {
+        require!(
+            !members.is_empty() && members.len() <= Self::MAX_MEMBERS,
+            ErrorCode::InvalidMembers
+        );
+        for (i, member) in members.iter().enumerate() {
+            require!(!members[..i].contains(member), ErrorCode::InvalidMembers);
+        }
+        require!(
+            threshold > 0 && usize::from(threshold) <= members.len(),
+            ErrorCode::InvalidThreshold
+        );
+        Ok(())
+    }
+    
+    /// Position of `key` in the member set, which is its approval bit
+    pub fn member_index(&self, key: &Pubkey) -> Result<usize> {
+        self.members.iter()
+            .position(|member| member == key)
+            .ok_or_else(|| error!(ErrorCode::NotAMember))
+    }
+}
+
+impl Transaction {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Multisig
+            8 + // Id
+            32 + // Proposer
+            4 + MultisigInstruction::MAX_INSTRUCTIONS * MultisigInstruction::space() + // Instructions
+            2 + // Approvals
+            4 + // Member set seqno
+            1 + // Status
+            8 + // Created at
+            8 + // Executed at
+            1 // Bump
+    }
+    
+    pub fn approval_count(&self) -> u8 {
+        self.approvals.count_ones() as u8
+    }
+    
+    /// Approvals only count against the member set they were given under
+    pub fn check_active(&self, multisig: &Multisig) -> Result<()> {
+        require!(self.status == TransactionStatus::Active, ErrorCode::TransactionNotActive);
+        require!(
+            self.member_set_seqno == multisig.member_set_seqno,
+            ErrorCode::StaleTransaction
+        );
+        Ok(())
+    }
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Numeric overflow occurred")]
+    NumericOverflow,
+    #[msg("Members must be unique, non-empty and at most 10")]
+    InvalidMembers,
+    #[msg("Threshold must be between 1 and the number of members")]
+    InvalidThreshold,
+    #[msg("Signer is not a multisig member")]
+    NotAMember,
+    #[msg("Transaction instructions are empty or too large")]
+    InvalidTransactionInstructions,
+    #[msg("Transactions may only call mcp, wale_token or multisig")]
+    UnsupportedTransactionTarget,
+    #[msg("Transaction is not active")]
+    TransactionNotActive,
+    #[msg("Member set changed since the transaction was created")]
+    StaleTransaction,
+    #[msg("Member has already approved")]
+    AlreadyApproved,
+    #[msg("Member has not approved")]
+    NotApproved,
+    #[msg("Not enough approvals to execute")]
+    ThresholdNotMet,
+    #[msg("Signer is not the proposer")]
+    UnauthorizedProposer,
+}
+
+// Events
+#[event]
+pub struct MembersChangedEvent {
+    pub multisig: Pubkey,
+    pub signer: Pubkey,
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    pub member_set_seqno: u32,
+}
+
+#[event]
+pub struct TransactionCreatedEvent {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub instruction_count: u8,
+}
+
+#[event]
+pub struct ApprovalChangedEvent {
+    pub transaction: Pubkey,
+    pub member: Pubkey,
+    pub approved: bool,
+    pub approval_count: u8,
+}
+
+#[event]
+pub struct TransactionExecutedEvent {
+    pub transaction: Pubkey,
+    pub executed_by: Pubkey,
+    pub approval_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransactionCancelledEvent {
+    pub transaction: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
+}
@@ -1,21 +1,198 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token;
-use anchor_spl::token::{Token, TokenAccount, Mint};
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::system_program;
+use anchor_spl::metadata::{self as token_metadata, Metadata};
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use mpl_token_metadata::state::DataV2;
+use spl_token_2022::extension::{
+    metadata_pointer, transfer_fee, transfer_hook, BaseStateWithExtensions, ExtensionType,
+    StateWithExtensions,
+};
+use spl_token_metadata_interface::state::{Field, TokenMetadata};
 
-declare_id!("WALEToken111111111111111111111111111111111");
+declare_id!("WALEToken1111111111111111111111111111111111");
 
 #[program]
 pub mod wale_token {
     use super::*;
 
     /// Initialize the WALE token
+    ///
+    /// Creates the mint under whichever token program is passed. Under
+    /// Token-2022 the mint carries the metadata-pointer and token-metadata
+    /// extensions, pointing at itself, so wallets read name, symbol and uri
+    /// from the mint; `transfer_fee` adds the transfer-fee extension with
+    /// withheld fees withdrawable only by this program, and
+    /// `transfer_hook_program` adds the transfer-hook extension pointing at
+    /// that program, with the authority as hook authority. Extensions can't
+    /// be added once the mint exists, so both are fixed here. Classic SPL mints
+    /// get a Metaplex metadata account instead, which requires `metadata`
+    /// and `token_metadata_program`. Either way the `token_info` PDA is the
+    /// metadata's update authority, so metadata follows `token_info.authority`
+    /// through authority transfers.
     pub fn initialize_token(
         ctx: Context<InitializeToken>,
         name: String,
         symbol: String,
         uri: String,
         decimals: u8,
+        transfer_fee: Option<TransferFeeParams>,
+        transfer_hook_program: Option<Pubkey>,
     ) -> Result<()> {
+        let token_program_id = ctx.accounts.token_program.key();
+        let is_token_2022 = token_program_id == spl_token_2022::ID;
+        let mint_key = ctx.accounts.mint.key();
+        let authority_key = ctx.accounts.authority.key();
+        let token_info_key = ctx.accounts.token_info.key();
+        
+        TokenInfo::validate_metadata(&name, &symbol, &uri)?;
+        require!(
+            (transfer_fee.is_none() && transfer_hook_program.is_none()) || is_token_2022,
+            ErrorCode::Token2022Required
+        );
+        if let Some(ref fee) = transfer_fee {
+            fee.validate()?;
+        }
+        
+        // Fixed-size extensions are sized up front; token metadata is
+        // variable-length and appended by its own initialize, so only its
+        // rent is prefunded
+        let mut extensions = Vec::new();
+        let mut metadata_len = 0;
+        if is_token_2022 {
+            extensions.push(ExtensionType::MetadataPointer);
+            if transfer_fee.is_some() {
+                extensions.push(ExtensionType::TransferFeeConfig);
+            }
+            if transfer_hook_program.is_some() {
+                extensions.push(ExtensionType::TransferHook);
+            }
+            let metadata = TokenMetadata {
+                update_authority: Some(token_info_key).try_into()?,
+                mint: mint_key,
+                name: name.clone(),
+                symbol: symbol.clone(),
+                uri: uri.clone(),
+                additional_metadata: vec![],
+            };
+            metadata_len = metadata.tlv_size_of()?;
+        }
+        let mint_len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extensions)?;
+        let lamports = Rent::get()?.minimum_balance(mint_len + metadata_len);
+        
+        system_program::create_account(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: ctx.accounts.mint.to_account_info(),
+                },
+            ),
+            lamports,
+            mint_len as u64,
+            &token_program_id,
+        )?;
+        
+        if is_token_2022 {
+            invoke(
+                &metadata_pointer::instruction::initialize(
+                    &token_program_id,
+                    &mint_key,
+                    Some(token_info_key),
+                    Some(mint_key),
+                )?,
+                &[ctx.accounts.mint.to_account_info()],
+            )?;
+            if let Some(ref fee) = transfer_fee {
+                invoke(
+                    &transfer_fee::instruction::initialize_transfer_fee_config(
+                        &token_program_id,
+                        &mint_key,
+                        Some(&authority_key),
+                        Some(&token_info_key),
+                        fee.fee_bps,
+                        fee.max_fee,
+                    )?,
+                    &[ctx.accounts.mint.to_account_info()],
+                )?;
+            }
+            if let Some(hook_program) = transfer_hook_program {
+                invoke(
+                    &transfer_hook::instruction::initialize(
+                        &token_program_id,
+                        &mint_key,
+                        Some(authority_key),
+                        Some(hook_program),
+                    )?,
+                    &[ctx.accounts.mint.to_account_info()],
+                )?;
+            }
+        }
+        
+        token_interface::initialize_mint2(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::InitializeMint2 {
+                    mint: ctx.accounts.mint.to_account_info(),
+                },
+            ),
+            decimals,
+            &authority_key,
+            None,
+        )?;
+        
+        if is_token_2022 {
+            invoke(
+                &spl_token_metadata_interface::instruction::initialize(
+                    &token_program_id,
+                    &mint_key,
+                    &token_info_key,
+                    &mint_key,
+                    &authority_key,
+                    name.clone(),
+                    symbol.clone(),
+                    uri.clone(),
+                ),
+                &[
+                    ctx.accounts.mint.to_account_info(),
+                    ctx.accounts.token_info.to_account_info(),
+                    ctx.accounts.authority.to_account_info(),
+                ],
+            )?;
+        } else {
+            require!(
+                name.len() <= TokenInfo::MAX_METAPLEX_NAME_LEN,
+                ErrorCode::MetadataTooLong
+            );
+            let (metadata, metadata_program) = match (
+                ctx.accounts.metadata.as_ref(),
+                ctx.accounts.token_metadata_program.as_ref(),
+            ) {
+                (Some(metadata), Some(program)) => (metadata, program),
+                _ => return err!(ErrorCode::MetadataAccountRequired),
+            };
+            token_metadata::create_metadata_accounts_v3(
+                CpiContext::new(
+                    metadata_program.to_account_info(),
+                    token_metadata::CreateMetadataAccountsV3 {
+                        metadata: metadata.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        mint_authority: ctx.accounts.authority.to_account_info(),
+                        payer: ctx.accounts.authority.to_account_info(),
+                        update_authority: ctx.accounts.token_info.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        rent: ctx.accounts.rent.to_account_info(),
+                    },
+                ),
+                TokenInfo::metaplex_data(&name, &symbol, &uri),
+                true,
+                false,
+                None,
+            )?;
+        }
+        
         let token_info = &mut ctx.accounts.token_info;
         
         // Initialize token metadata
@@ -29,6 +206,10 @@ pub mod wale_token {
         token_info.total_supply = 0;
         token_info.circulating_supply = 0;
         token_info.is_frozen = false;
+        token_info.timelock = None;
+        token_info.total_emitted = 0;
+        token_info.total_burned = 0;
+        token_info.burned_by_protocol = 0;
         
         emit!(TokenInitializedEvent {
             mint: token_info.mint,
@@ -41,7 +222,126 @@ pub mod wale_token {
         Ok(())
     }
     
+    /// Update name, symbol or uri in `TokenInfo` and in the mint's
+    /// wallet-visible metadata together
+    ///
+    /// Token-2022 mints are updated through the token-metadata extension,
+    /// with the authority topping up rent when the metadata grows. Classic
+    /// mints are updated through their Metaplex metadata account. Both are
+    /// signed by the `token_info` PDA as update authority.
+    pub fn update_token_metadata(
+        ctx: Context<UpdateTokenMetadata>,
+        name: Option<String>,
+        symbol: Option<String>,
+        uri: Option<String>,
+    ) -> Result<()> {
+        let token_info = &ctx.accounts.token_info;
+        let token_program_id = ctx.accounts.token_program.key();
+        let mint_key = ctx.accounts.mint.key();
+        let seeds = &[
+            b"token_info".as_ref(),
+            mint_key.as_ref(),
+            &[*ctx.bumps.get("token_info").unwrap()],
+        ];
+        let signer = &[&seeds[..]];
+        
+        require!(
+            ctx.accounts.authority.key() == token_info.authority,
+            ErrorCode::UnauthorizedOperation
+        );
+        
+        let new_name = name.clone().unwrap_or_else(|| token_info.name.clone());
+        let new_symbol = symbol.clone().unwrap_or_else(|| token_info.symbol.clone());
+        let new_uri = uri.clone().unwrap_or_else(|| token_info.uri.clone());
+        TokenInfo::validate_metadata(&new_name, &new_symbol, &new_uri)?;
+        
+        if token_program_id == spl_token_2022::ID {
+            let old_len = token_info.name.len() + token_info.symbol.len() + token_info.uri.len();
+            let new_len = new_name.len() + new_symbol.len() + new_uri.len();
+            if new_len > old_len {
+                let mint_info = ctx.accounts.mint.to_account_info();
+                let required = Rent::get()?.minimum_balance(mint_info.data_len() + new_len - old_len);
+                let top_up = required.saturating_sub(mint_info.lamports());
+                if top_up > 0 {
+                    system_program::transfer(
+                        CpiContext::new(
+                            ctx.accounts.system_program.to_account_info(),
+                            system_program::Transfer {
+                                from: ctx.accounts.authority.to_account_info(),
+                                to: mint_info,
+                            },
+                        ),
+                        top_up,
+                    )?;
+                }
+            }
+            
+            let updates = [(Field::Name, name), (Field::Symbol, symbol), (Field::Uri, uri)];
+            for (field, value) in updates {
+                if let Some(value) = value {
+                    invoke_signed(
+                        &spl_token_metadata_interface::instruction::update_field(
+                            &token_program_id,
+                            &mint_key,
+                            &token_info.key(),
+                            field,
+                            value,
+                        ),
+                        &[
+                            ctx.accounts.mint.to_account_info(),
+                            token_info.to_account_info(),
+                        ],
+                        signer,
+                    )?;
+                }
+            }
+        } else {
+            require!(
+                new_name.len() <= TokenInfo::MAX_METAPLEX_NAME_LEN,
+                ErrorCode::MetadataTooLong
+            );
+            let (metadata, metadata_program) = match (
+                ctx.accounts.metadata.as_ref(),
+                ctx.accounts.token_metadata_program.as_ref(),
+            ) {
+                (Some(metadata), Some(program)) => (metadata, program),
+                _ => return err!(ErrorCode::MetadataAccountRequired),
+            };
+            token_metadata::update_metadata_accounts_v2(
+                CpiContext::new_with_signer(
+                    metadata_program.to_account_info(),
+                    token_metadata::UpdateMetadataAccountsV2 {
+                        metadata: metadata.to_account_info(),
+                        update_authority: token_info.to_account_info(),
+                    },
+                    signer,
+                ),
+                None,
+                Some(TokenInfo::metaplex_data(&new_name, &new_symbol, &new_uri)),
+                None,
+                None,
+            )?;
+        }
+        
+        let token_info = &mut ctx.accounts.token_info;
+        token_info.name = new_name;
+        token_info.symbol = new_symbol;
+        token_info.uri = new_uri;
+        
+        emit!(TokenMetadataUpdatedEvent {
+            mint: token_info.mint,
+            name: token_info.name.clone(),
+            symbol: token_info.symbol.clone(),
+            uri: token_info.uri.clone(),
+        });
+        
+        Ok(())
+    }
+    
     /// Mint new tokens
+    ///
+    /// Only while the authority still holds the mint authority; once
+    /// emissions start, new supply comes from the schedule alone.
     pub fn mint_tokens(
         ctx: Context<MintTokens>,
         amount: u64,
@@ -57,8 +357,15 @@ pub mod wale_token {
         // Verify token not frozen
         require!(!token_info.is_frozen, ErrorCode::TokenFrozen);
         
+        // Once emissions are enabled the mint authority is the PDA and only
+        // the schedule mints
+        require!(
+            ctx.accounts.mint.mint_authority != COption::Some(ctx.accounts.mint_authority.key()),
+            ErrorCode::EmissionScheduleActive
+        );
+        
         // Mint tokens using SPL token program
-        let cpi_accounts = token::MintTo {
+        let cpi_accounts = token_interface::MintTo {
             mint: ctx.accounts.mint.to_account_info(),
             to: ctx.accounts.destination.to_account_info(),
             authority: ctx.accounts.authority.to_account_info(),
@@ -67,13 +374,10 @@ pub mod wale_token {
         let cpi_program = ctx.accounts.token_program.to_account_info();
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
         
-        token::mint_to(cpi_ctx, amount)?;
+        token_interface::mint_to(cpi_ctx, amount)?;
         
         // Update token info
-        token_info.total_supply = token_info.total_supply.checked_add(amount)
-            .ok_or(ErrorCode::NumericOverflow)?;
-        token_info.circulating_supply = token_info.circulating_supply.checked_add(amount)
-            .ok_or(ErrorCode::NumericOverflow)?;
+        token_info.record_mint(amount)?;
         
         emit!(TokensMintedEvent {
             mint: token_info.mint,
@@ -93,7 +397,7 @@ pub mod wale_token {
         let token_info = &mut ctx.accounts.token_info;
         
         // Burn tokens using SPL token program
-        let cpi_accounts = token::Burn {
+        let cpi_accounts = token_interface::Burn {
             mint: ctx.accounts.mint.to_account_info(),
             from: ctx.accounts.source.to_account_info(),
             authority: ctx.accounts.owner.to_account_info(),
@@ -102,11 +406,10 @@ pub mod wale_token {
         let cpi_program = ctx.accounts.token_program.to_account_info();
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
         
-        token::burn(cpi_ctx, amount)?;
+        token_interface::burn(cpi_ctx, amount)?;
         
         // Update token info
-        token_info.circulating_supply = token_info.circulating_supply.checked_sub(amount)
-            .ok_or(ErrorCode::NumericOverflow)?;
+        token_info.record_burn(amount)?;
         
         emit!(TokensBurnedEvent {
             mint: token_info.mint,
@@ -118,6 +421,32 @@ pub mod wale_token {
         Ok(())
     }
     
+    /// Reconcile `TokenInfo` with the mint's actual supply; anyone may
+    /// crank
+    ///
+    /// Supply above `circulating_supply` was minted outside the program and
+    /// supply below it was burned outside `burn_tokens`; either is recorded
+    /// as if it had gone through the program.
+    pub fn sync_supply(
+        ctx: Context<SyncSupply>,
+    ) -> Result<()> {
+        let token_info = &mut ctx.accounts.token_info;
+        let actual_supply = ctx.accounts.mint.supply;
+        let recorded_supply = token_info.circulating_supply;
+        
+        let (minted_outside, burned_outside) = token_info.reconcile_supply(actual_supply)?;
+        
+        emit!(SupplySyncedEvent {
+            mint: token_info.mint,
+            recorded_supply,
+            actual_supply,
+            minted_outside,
+            burned_outside,
+        });
+        
+        Ok(())
+    }
+    
     /// Freeze/unfreeze all token operations
     pub fn set_freeze_state(
         ctx: Context<SetFreezeState>,
@@ -130,6 +459,7 @@ pub mod wale_token {
             ctx.accounts.authority.key() == token_info.authority,
             ErrorCode::UnauthorizedOperation
         );
+        require!(token_info.timelock.is_none(), ErrorCode::TimelockRequired);
         
         // Set freeze state
         token_info.is_frozen = is_frozen;
@@ -154,6 +484,7 @@ pub mod wale_token {
             ctx.accounts.authority.key() == token_info.authority,
             ErrorCode::UnauthorizedOperation
         );
+        require!(token_info.timelock.is_none(), ErrorCode::TimelockRequired);
         
         // Store old authority for event
         let old_authority = token_info.authority;
@@ -169,6 +500,543 @@ pub mod wale_token {
         
         Ok(())
     }
+    
+    /// Put freeze and authority changes behind a timelock
+    ///
+    /// Once enabled, `set_freeze_state` and `transfer_authority` are
+    /// rejected and the same changes must go through `queue_admin_action`;
+    /// instructions with accounts of their own, like `withdraw_treasury`,
+    /// then only run by consuming a matching queued action.
+    pub fn initialize_timelock(
+        ctx: Context<InitializeTimelock>,
+        guardian: Pubkey,
+        delay: i64,
+    ) -> Result<()> {
+        let token_info = &mut ctx.accounts.token_info;
+        let timelock = &mut ctx.accounts.timelock;
+        
+        require!(
+            ctx.accounts.authority.key() == token_info.authority,
+            ErrorCode::UnauthorizedOperation
+        );
+        Timelock::validate_delay(delay)?;
+        
+        timelock.mint = token_info.mint;
+        timelock.guardian = guardian;
+        timelock.delay = delay;
+        timelock.next_action_id = 0;
+        timelock.authority_nonce = 0;
+        timelock.bump = *ctx.bumps.get("timelock").unwrap();
+        
+        token_info.timelock = Some(timelock.key());
+        
+        emit!(TimelockUpdatedEvent {
+            mint: timelock.mint,
+            guardian,
+            delay,
+        });
+        
+        Ok(())
+    }
+    
+    /// Schedule an admin action to run after the timelock delay
+    pub fn queue_admin_action(
+        ctx: Context<QueueAdminAction>,
+        action: AdminAction,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let token_info = &ctx.accounts.token_info;
+        let timelock = &mut ctx.accounts.timelock;
+        let queued = &mut ctx.accounts.queued_action;
+        
+        require!(
+            ctx.accounts.authority.key() == token_info.authority,
+            ErrorCode::UnauthorizedOperation
+        );
+        match action {
+            AdminAction::UpdateTimelock { delay, .. } => Timelock::validate_delay(delay)?,
+            AdminAction::InitializeEmission { ref params, .. } => params.validate()?,
+            AdminAction::SetBurnPolicy { burn_bps, epoch_duration } => {
+                BurnPolicy::validate(burn_bps, epoch_duration)?
+            }
+            _ => {}
+        }
+        
+        queued.timelock = timelock.key();
+        queued.id = timelock.next_action_id;
+        queued.action = action;
+        queued.eta = now.checked_add(timelock.delay)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        queued.status = ActionStatus::Queued;
+        queued.queued_by = ctx.accounts.authority.key();
+        queued.authority_nonce = timelock.authority_nonce;
+        queued.bump = *ctx.bumps.get("queued_action").unwrap();
+        
+        timelock.next_action_id = timelock.next_action_id.checked_add(1)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(AdminActionQueuedEvent {
+            mint: timelock.mint,
+            id: queued.id,
+            action: queued.action.clone(),
+            eta: queued.eta,
+        });
+        
+        Ok(())
+    }
+    
+    /// Cancel a queued admin action; the guardian or the authority may cancel
+    pub fn cancel_admin_action(
+        ctx: Context<CancelAdminAction>,
+    ) -> Result<()> {
+        let queued = &mut ctx.accounts.queued_action;
+        let signer = ctx.accounts.signer.key();
+        
+        require!(
+            signer == ctx.accounts.timelock.guardian || signer == ctx.accounts.token_info.authority,
+            ErrorCode::UnauthorizedOperation
+        );
+        require!(queued.status == ActionStatus::Queued, ErrorCode::ActionNotQueued);
+        
+        queued.status = ActionStatus::Cancelled;
+        
+        emit!(AdminActionCancelledEvent {
+            mint: ctx.accounts.timelock.mint,
+            id: queued.id,
+            cancelled_by: signer,
+        });
+        
+        Ok(())
+    }
+    
+    /// Apply a queued admin action once its ETA has passed; anyone may crank
+    ///
+    /// Actions queued before the last authority change are void, so a
+    /// replaced authority can't leave changes behind for later.
+    pub fn execute_admin_action(
+        ctx: Context<ExecuteAdminAction>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let token_info = &mut ctx.accounts.token_info;
+        let timelock = &mut ctx.accounts.timelock;
+        let queued = &mut ctx.accounts.queued_action;
+        
+        require!(queued.status == ActionStatus::Queued, ErrorCode::ActionNotQueued);
+        require!(now >= queued.eta, ErrorCode::TimelockNotElapsed);
+        require!(
+            queued.authority_nonce == timelock.authority_nonce,
+            ErrorCode::StaleAdminAction
+        );
+        
+        match queued.action.clone() {
+            AdminAction::SetFreezeState { is_frozen } => {
+                token_info.is_frozen = is_frozen;
+                emit!(TokenFreezeStateChangedEvent {
+                    mint: token_info.mint,
+                    is_frozen,
+                });
+            }
+            AdminAction::TransferAuthority { new_authority } => {
+                let old_authority = token_info.authority;
+                token_info.authority = new_authority;
+                timelock.authority_nonce = timelock.authority_nonce.checked_add(1)
+                    .ok_or(ErrorCode::NumericOverflow)?;
+                emit!(AuthorityTransferredEvent {
+                    mint: token_info.mint,
+                    old_authority,
+                    new_authority,
+                });
+            }
+            AdminAction::UpdateTimelock { guardian, delay } => {
+                timelock.guardian = guardian;
+                timelock.delay = delay;
+                emit!(TimelockUpdatedEvent {
+                    mint: timelock.mint,
+                    guardian,
+                    delay,
+                });
+            }
+            AdminAction::WithdrawTreasury { .. }
+            | AdminAction::InitializeEmission { .. }
+            | AdminAction::SetBurnPolicy { .. } => {
+                return err!(ErrorCode::ActionRequiresInstruction);
+            }
+        }
+        
+        queued.status = ActionStatus::Executed;
+        
+        emit!(AdminActionExecutedEvent {
+            mint: timelock.mint,
+            id: queued.id,
+            action: queued.action.clone(),
+        });
+        
+        Ok(())
+    }
+    
+    /// Hand the mint authority to the program's PDA and start a reward
+    /// emission schedule
+    ///
+    /// An admin action: once the timelock is enabled it runs only by
+    /// consuming a queued `InitializeEmission`. `mint_tokens` stops working
+    /// afterwards, so the schedule is the only source of new supply.
+    pub fn initialize_emission(
+        ctx: Context<InitializeEmission>,
+        params: EmissionParams,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let token_info = &ctx.accounts.token_info;
+        let schedule = &mut ctx.accounts.emission_schedule;
+        
+        require!(
+            ctx.accounts.authority.key() == token_info.authority,
+            ErrorCode::UnauthorizedOperation
+        );
+        params.validate()?;
+        token_info.authorize_admin_action(
+            ctx.accounts.timelock.as_ref(),
+            ctx.accounts.queued_action.as_mut(),
+            &AdminAction::InitializeEmission {
+                rewards_vault: ctx.accounts.rewards_vault.key(),
+                params: params.clone(),
+            },
+            now,
+        )?;
+        
+        if ctx.accounts.mint.mint_authority != COption::Some(ctx.accounts.mint_authority.key()) {
+            token_interface::set_authority(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token_interface::SetAuthority {
+                        current_authority: ctx.accounts.authority.to_account_info(),
+                        account_or_mint: ctx.accounts.mint.to_account_info(),
+                    },
+                ),
+                AuthorityType::MintTokens,
+                Some(ctx.accounts.mint_authority.key()),
+            )?;
+        }
+        
+        schedule.mint = token_info.mint;
+        schedule.rewards_vault = ctx.accounts.rewards_vault.key();
+        schedule.params = params;
+        schedule.next_epoch = 0;
+        schedule.emitted = 0;
+        schedule.bump = *ctx.bumps.get("emission_schedule").unwrap();
+        
+        emit!(EmissionScheduleCreatedEvent {
+            mint: schedule.mint,
+            rewards_vault: schedule.rewards_vault,
+            params: schedule.params.clone(),
+        });
+        
+        Ok(())
+    }
+    
+    /// Mint the next due epoch's budget into the rewards vault; anyone may
+    /// crank
+    ///
+    /// Each call emits one epoch, so missed epochs are caught up by
+    /// cranking repeatedly.
+    pub fn crank_emission(
+        ctx: Context<CrankEmission>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let token_info = &mut ctx.accounts.token_info;
+        let schedule = &mut ctx.accounts.emission_schedule;
+        
+        require!(!token_info.is_frozen, ErrorCode::TokenFrozen);
+        let epoch = schedule.next_epoch;
+        require!(epoch < schedule.params.epochs_started(now), ErrorCode::EpochNotReached);
+        
+        let amount = schedule.params.budget(epoch)
+            .min(schedule.params.max_total_emission - schedule.emitted);
+        require!(amount > 0, ErrorCode::EmissionExhausted);
+        
+        let mint_key = ctx.accounts.mint.key();
+        let seeds = &[
+            b"mint_authority".as_ref(),
+            mint_key.as_ref(),
+            &[*ctx.bumps.get("mint_authority").unwrap()],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::MintTo {
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.rewards_vault.to_account_info(),
+            authority: ctx.accounts.mint_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        token_interface::mint_to(cpi_ctx, amount)?;
+        
+        schedule.next_epoch = epoch + 1;
+        schedule.emitted += amount;
+        token_info.record_mint(amount)?;
+        token_info.total_emitted = token_info.total_emitted.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(EmissionCrankedEvent {
+            mint: token_info.mint,
+            epoch,
+            amount,
+            total_emitted: token_info.total_emitted,
+            circulating_supply: token_info.circulating_supply,
+        });
+        
+        Ok(())
+    }
+    
+    /// Set the share of treasury fee revenue burned each epoch
+    ///
+    /// Revenue is whatever the treasury balance grew by since the previous
+    /// burn, e.g. harvested transfer fees. An admin action: once the
+    /// timelock is enabled it runs only by consuming a queued
+    /// `SetBurnPolicy`.
+    pub fn set_burn_policy(
+        ctx: Context<SetBurnPolicy>,
+        burn_bps: u16,
+        epoch_duration: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let policy = &mut ctx.accounts.burn_policy;
+        
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.token_info.authority,
+            ErrorCode::UnauthorizedOperation
+        );
+        BurnPolicy::validate(burn_bps, epoch_duration)?;
+        ctx.accounts.token_info.authorize_admin_action(
+            ctx.accounts.timelock.as_ref(),
+            ctx.accounts.queued_action.as_mut(),
+            &AdminAction::SetBurnPolicy { burn_bps, epoch_duration },
+            now,
+        )?;
+        
+        if policy.mint == Pubkey::default() {
+            policy.mint = ctx.accounts.mint.key();
+            policy.treasury = ctx.accounts.treasury.key();
+            policy.start_ts = now;
+            policy.epoch_duration = epoch_duration;
+            policy.next_epoch = 0;
+            policy.last_treasury_balance = ctx.accounts.treasury.amount;
+            policy.total_fee_revenue = 0;
+            policy.bump = *ctx.bumps.get("burn_policy").unwrap();
+        }
+        policy.burn_bps = burn_bps;
+        policy.set_epoch_duration(epoch_duration, now);
+        
+        emit!(BurnPolicyUpdatedEvent {
+            mint: policy.mint,
+            treasury: policy.treasury,
+            burn_bps,
+            epoch_duration,
+        });
+        
+        Ok(())
+    }
+    
+    /// Burn the policy's share of fee revenue collected since the last
+    /// burn, signed by the treasury PDA; anyone may crank once per epoch
+    pub fn burn_protocol_fees(
+        ctx: Context<BurnProtocolFees>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let token_info = &mut ctx.accounts.token_info;
+        let policy = &mut ctx.accounts.burn_policy;
+        
+        let epoch = policy.current_epoch(now);
+        require!(epoch >= policy.next_epoch, ErrorCode::EpochNotReached);
+        
+        let balance = ctx.accounts.treasury.amount;
+        let fee_revenue = balance.saturating_sub(policy.last_treasury_balance);
+        let burned = (u128::from(fee_revenue) * u128::from(policy.burn_bps)
+            / u128::from(BurnPolicy::MAX_BPS)) as u64;
+        
+        if burned > 0 {
+            let mint_key = ctx.accounts.mint.key();
+            let seeds = &[
+                b"treasury".as_ref(),
+                mint_key.as_ref(),
+                &[*ctx.bumps.get("treasury_authority").unwrap()],
+            ];
+            let signer = &[&seeds[..]];
+            let cpi_accounts = token_interface::Burn {
+                mint: ctx.accounts.mint.to_account_info(),
+                from: ctx.accounts.treasury.to_account_info(),
+                authority: ctx.accounts.treasury_authority.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            );
+            token_interface::burn(cpi_ctx, burned)?;
+            
+            token_info.record_burn(burned)?;
+            token_info.burned_by_protocol = token_info.burned_by_protocol.checked_add(burned)
+                .ok_or(ErrorCode::NumericOverflow)?;
+        }
+        
+        policy.next_epoch = epoch + 1;
+        policy.last_treasury_balance = balance - burned;
+        policy.total_fee_revenue = policy.total_fee_revenue.checked_add(fee_revenue)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(ProtocolBurnEvent {
+            mint: token_info.mint,
+            epoch,
+            fee_revenue,
+            burned,
+            burned_by_protocol: token_info.burned_by_protocol,
+            circulating_supply: token_info.circulating_supply,
+        });
+        
+        Ok(())
+    }
+    
+    /// Sweep withheld transfer fees into the treasury; anyone may crank
+    ///
+    /// Fees withheld in the token accounts passed as `remaining_accounts`
+    /// are first harvested into the mint, then everything withheld on the
+    /// mint is withdrawn to the treasury.
+    pub fn harvest_transfer_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, HarvestTransferFees<'info>>,
+    ) -> Result<()> {
+        let token_program_id = ctx.accounts.token_program.key();
+        let mint_info = ctx.accounts.mint.to_account_info();
+        
+        if !ctx.remaining_accounts.is_empty() {
+            let sources: Vec<&Pubkey> = ctx.remaining_accounts.iter().map(|account| account.key).collect();
+            let mut accounts = vec![mint_info.clone()];
+            accounts.extend(ctx.remaining_accounts.iter().cloned());
+            invoke(
+                &transfer_fee::instruction::harvest_withheld_tokens_to_mint(
+                    &token_program_id,
+                    &mint_info.key(),
+                    &sources,
+                )?,
+                &accounts,
+            )?;
+        }
+        
+        let amount: u64 = {
+            let data = mint_info.try_borrow_data()?;
+            let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+            let config = mint.get_extension::<transfer_fee::TransferFeeConfig>()
+                .map_err(|_| error!(ErrorCode::TransferFeeNotEnabled))?;
+            config.withheld_amount.into()
+        };
+        
+        if amount > 0 {
+            let mint_key = mint_info.key();
+            let seeds = &[
+                b"token_info".as_ref(),
+                mint_key.as_ref(),
+                &[*ctx.bumps.get("token_info").unwrap()],
+            ];
+            invoke_signed(
+                &transfer_fee::instruction::withdraw_withheld_tokens_from_mint(
+                    &token_program_id,
+                    &mint_key,
+                    &ctx.accounts.treasury.key(),
+                    &ctx.accounts.token_info.key(),
+                    &[],
+                )?,
+                &[
+                    mint_info.clone(),
+                    ctx.accounts.treasury.to_account_info(),
+                    ctx.accounts.token_info.to_account_info(),
+                ],
+                &[&seeds[..]],
+            )?;
+        }
+        
+        emit!(TransferFeesHarvestedEvent {
+            mint: mint_info.key(),
+            treasury: ctx.accounts.treasury.key(),
+            amount,
+            source_count: ctx.remaining_accounts.len() as u16,
+        });
+        
+        Ok(())
+    }
+    
+    /// Spend `amount` from a fee treasury, signed by the treasury PDA
+    ///
+    /// Runs directly for the authority while no timelock is set, otherwise
+    /// only by consuming a queued `WithdrawTreasury` action. From the burn
+    /// policy's treasury only revenue a burn has already seen may leave, so
+    /// the burn share of newer fees stays behind. Accounts the mint's
+    /// transfer hook needs, if any, go in `remaining_accounts`.
+    pub fn withdraw_treasury<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawTreasury<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let treasury_key = ctx.accounts.treasury.key();
+        let destination_key = ctx.accounts.destination.key();
+        
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.token_info.authority,
+            ErrorCode::UnauthorizedOperation
+        );
+        ctx.accounts.token_info.authorize_admin_action(
+            ctx.accounts.timelock.as_ref(),
+            ctx.accounts.queued_action.as_mut(),
+            &AdminAction::WithdrawTreasury {
+                treasury: treasury_key,
+                destination: destination_key,
+                amount,
+            },
+            now,
+        )?;
+        
+        let policy_info = ctx.accounts.burn_policy.to_account_info();
+        if !policy_info.data_is_empty() {
+            let mut policy = Account::<BurnPolicy>::try_from(&policy_info)?;
+            if policy.treasury == treasury_key {
+                require!(
+                    amount <= policy.last_treasury_balance,
+                    ErrorCode::UnburnedFeeRevenue
+                );
+                policy.last_treasury_balance -= amount;
+                policy.exit(&crate::ID)?;
+            }
+        }
+        
+        let mint_key = ctx.accounts.mint.key();
+        let seeds = &[
+            b"treasury".as_ref(),
+            mint_key.as_ref(),
+            &[*ctx.bumps.get("treasury_authority").unwrap()],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.treasury.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.treasury_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+        
+        emit!(TreasuryWithdrawnEvent {
+            mint: mint_key,
+            treasury: treasury_key,
+            destination: destination_key,
+            amount,
+        });
+        
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
@@ -185,10 +1053,44 @@ pub struct InitializeToken<'info> {
     )]
     pub token_info: Account<'info, TokenInfo>,
     
-    pub mint: Account<'info, Mint>,
+    /// New mint account, created and initialized by the instruction
+    #[account(mut)]
+    pub mint: Signer<'info>,
+    
+    /// CHECK: Metaplex metadata PDA for the mint, derivation checked by the
+    /// token metadata program; only used for classic SPL mints
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+    
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTokenMetadata<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_info", mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Account<'info, TokenInfo>,
+    
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Metaplex metadata PDA for the mint, checked by the token
+    /// metadata program; only used for classic SPL mints
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
     
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
@@ -204,12 +1106,19 @@ pub struct MintTokens<'info> {
     pub token_info: Account<'info, TokenInfo>,
     
     #[account(mut)]
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: PDA that holds the mint authority once emissions are enabled
+    #[account(
+        seeds = [b"mint_authority", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
     
     #[account(mut)]
-    pub destination: Account<'info, TokenAccount>,
+    pub destination: InterfaceAccount<'info, TokenAccount>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
@@ -225,15 +1134,27 @@ pub struct BurnTokens<'info> {
     pub token_info: Account<'info, TokenInfo>,
     
     #[account(mut)]
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         mut,
         constraint = source.owner == owner.key(),
     )]
-    pub source: Account<'info, TokenAccount>,
+    pub source: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SyncSupply<'info> {
+    #[account(
+        mut,
+        seeds = [b"token_info", mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Account<'info, TokenInfo>,
     
-    pub token_program: Program<'info, Token>,
+    pub mint: InterfaceAccount<'info, Mint>,
 }
 
 #[derive(Accounts)]
@@ -248,7 +1169,7 @@ pub struct SetFreezeState<'info> {
     )]
     pub token_info: Account<'info, TokenInfo>,
     
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
 }
 
 #[derive(Accounts)]
@@ -263,41 +1184,726 @@ pub struct TransferAuthority<'info> {
     )]
     pub token_info: Account<'info, TokenInfo>,
     
-    pub mint: Account<'info, Mint>,
-}
-
-#[account]
-pub struct TokenInfo {
-    pub name: String,           // Token name
-    pub symbol: String,         // Token symbol
-    pub uri: String,            // Metadata URI
-    pub decimals: u8,           // Decimal precision
-    pub authority: Pubkey,      // Authority that can mint/freeze
-    pub mint: Pubkey,           // Mint address
-    pub created_at: i64,        // Creation timestamp
-    pub total_supply: u64,      // Total tokens ever minted
-    pub circulating_supply: u64, // Current supply (minted - burned)
-    pub is_frozen: bool,        // Freeze state
+    pub mint: InterfaceAccount<'info, Mint>,
 }
 
-impl TokenInfo {
-    pub fn space() -> usize {
-        // Fixed size fields
-        let fixed_size = 8 + // Discriminator
-            1 + // Decimals
-            32 + // Authority pubkey
-            32 + // Mint pubkey
-            8 + // Created timestamp
-            8 + // Total supply
-            8 + // Circulating supply
-            1; // Is frozen
-        
-        // Variable size fields with maximum lengths
-        let variable_size = 4 + 50 +  // name (String with max 50 chars)
-            4 + 10 +                  // symbol (String with max 10 chars)
-            4 + 200;                  // uri (String with max 200 chars)
-            
-        fixed_size + variable_size
+#[derive(Accounts)]
+pub struct InitializeTimelock<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_info", mint.key().as_ref()],
+        bump,
+        constraint = token_info.timelock.is_none() @ ErrorCode::TimelockAlreadyEnabled,
+    )]
+    pub token_info: Account<'info, TokenInfo>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = Timelock::space(),
+        seeds = [b"timelock", mint.key().as_ref()],
+        bump
+    )]
+    pub timelock: Account<'info, Timelock>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QueueAdminAction<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_info", mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Account<'info, TokenInfo>,
+    
+    #[account(
+        mut,
+        seeds = [b"timelock", mint.key().as_ref()],
+        bump = timelock.bump,
+    )]
+    pub timelock: Account<'info, Timelock>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = QueuedAction::space(),
+        seeds = [
+            b"queued_action",
+            timelock.key().as_ref(),
+            &timelock.next_action_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub queued_action: Account<'info, QueuedAction>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAdminAction<'info> {
+    pub signer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_info", mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Account<'info, TokenInfo>,
+    
+    #[account(
+        seeds = [b"timelock", mint.key().as_ref()],
+        bump = timelock.bump,
+    )]
+    pub timelock: Account<'info, Timelock>,
+    
+    #[account(
+        mut,
+        constraint = queued_action.timelock == timelock.key(),
+    )]
+    pub queued_action: Account<'info, QueuedAction>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteAdminAction<'info> {
+    #[account(
+        mut,
+        seeds = [b"token_info", mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Account<'info, TokenInfo>,
+    
+    #[account(
+        mut,
+        seeds = [b"timelock", mint.key().as_ref()],
+        bump = timelock.bump,
+    )]
+    pub timelock: Account<'info, Timelock>,
+    
+    #[account(
+        mut,
+        constraint = queued_action.timelock == timelock.key(),
+    )]
+    pub queued_action: Account<'info, QueuedAction>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeEmission<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_info", mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Account<'info, TokenInfo>,
+    
+    /// Required once the timelock is enabled
+    #[account(
+        seeds = [b"timelock", mint.key().as_ref()],
+        bump = timelock.bump,
+    )]
+    pub timelock: Option<Account<'info, Timelock>>,
+    
+    /// Queued schedule to consume, required once the timelock is enabled
+    #[account(mut)]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = EmissionSchedule::space(),
+        seeds = [b"emission", mint.key().as_ref()],
+        bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: PDA that becomes the mint authority, holds no data
+    #[account(
+        seeds = [b"mint_authority", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(constraint = rewards_vault.mint == mint.key() @ ErrorCode::InvalidRewardsVault)]
+    pub rewards_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CrankEmission<'info> {
+    #[account(
+        mut,
+        seeds = [b"token_info", mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Account<'info, TokenInfo>,
+    
+    #[account(
+        mut,
+        seeds = [b"emission", mint.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: PDA holding the mint authority, holds no data
+    #[account(
+        seeds = [b"mint_authority", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, address = emission_schedule.rewards_vault)]
+    pub rewards_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetBurnPolicy<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_info", mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Account<'info, TokenInfo>,
+    
+    /// Required once the timelock is enabled
+    #[account(
+        seeds = [b"timelock", mint.key().as_ref()],
+        bump = timelock.bump,
+    )]
+    pub timelock: Option<Account<'info, Timelock>>,
+    
+    /// Queued policy change to consume, required once the timelock is
+    /// enabled
+    #[account(mut)]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = BurnPolicy::space(),
+        seeds = [b"burn_policy", mint.key().as_ref()],
+        bump
+    )]
+    pub burn_policy: Account<'info, BurnPolicy>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: PDA that owns the treasury token account, holds no data
+    #[account(
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump
+    )]
+    pub treasury_authority: UncheckedAccount<'info>,
+    
+    #[account(
+        constraint = treasury.mint == mint.key() @ ErrorCode::InvalidTreasury,
+        constraint = treasury.owner == treasury_authority.key() @ ErrorCode::InvalidTreasury,
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BurnProtocolFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"token_info", mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Account<'info, TokenInfo>,
+    
+    #[account(
+        mut,
+        seeds = [b"burn_policy", mint.key().as_ref()],
+        bump = burn_policy.bump,
+    )]
+    pub burn_policy: Account<'info, BurnPolicy>,
+    
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: PDA that owns the treasury token account, holds no data
+    #[account(
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump
+    )]
+    pub treasury_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, address = burn_policy.treasury)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct HarvestTransferFees<'info> {
+    #[account(
+        seeds = [b"token_info", mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Account<'info, TokenInfo>,
+    
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: PDA that owns the treasury token account, holds no data
+    #[account(
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump
+    )]
+    pub treasury_authority: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = treasury.mint == mint.key() @ ErrorCode::InvalidTreasury,
+        constraint = treasury.owner == treasury_authority.key() @ ErrorCode::InvalidTreasury,
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_info", mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Account<'info, TokenInfo>,
+    
+    /// Required once the timelock is enabled
+    #[account(
+        seeds = [b"timelock", mint.key().as_ref()],
+        bump = timelock.bump,
+    )]
+    pub timelock: Option<Account<'info, Timelock>>,
+    
+    /// Queued withdrawal to consume, required once the timelock is enabled
+    #[account(mut)]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+    
+    /// CHECK: Burn policy PDA, read and updated only when it exists
+    #[account(
+        mut,
+        seeds = [b"burn_policy", mint.key().as_ref()],
+        bump
+    )]
+    pub burn_policy: UncheckedAccount<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: PDA that owns the treasury token account, holds no data
+    #[account(
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump
+    )]
+    pub treasury_authority: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = treasury.mint == mint.key() @ ErrorCode::InvalidTreasury,
+        constraint = treasury.owner == treasury_authority.key() @ ErrorCode::InvalidTreasury,
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = destination.mint == mint.key())]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[account]
+pub struct TokenInfo {
+    pub name: String,           // Token name
+    pub symbol: String,         // Token symbol
+    pub uri: String,            // Metadata URI
+    pub decimals: u8,           // Decimal precision
+    pub authority: Pubkey,      // Authority that can mint/freeze
+    pub mint: Pubkey,           // Mint address
+    pub created_at: i64,        // Creation timestamp
+    pub total_supply: u64,      // Total tokens ever minted
+    pub circulating_supply: u64, // Current supply (minted - burned)
+    pub is_frozen: bool,        // Freeze state
+    pub timelock: Option<Pubkey>, // Timelock gating admin changes, if enabled
+    pub total_emitted: u64,     // Tokens minted by the emission schedule
+    pub total_burned: u64,      // Tokens ever burned
+    pub burned_by_protocol: u64, // Tokens burned from the treasury
+}
+
+#[account]
+pub struct Timelock {
+    pub mint: Pubkey,           // Mint the timelock guards
+    pub guardian: Pubkey,       // May cancel queued actions
+    pub delay: i64,             // Seconds between queueing and execution
+    pub next_action_id: u64,    // Id of the next queued action
+    pub authority_nonce: u64,   // Bumped on every authority change
+    pub bump: u8,               // PDA bump
+}
+
+/// Reward emission curve: `initial_budget` per epoch, halved every
+/// `halving_interval` epochs, never exceeding `max_total_emission` in total
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct EmissionParams {
+    pub start_ts: i64,          // Start of epoch 0
+    pub epoch_duration: i64,    // Seconds per epoch
+    pub initial_budget: u64,    // Tokens minted for epoch 0
+    pub halving_interval: u64,  // Epochs between halvings, 0 for a flat budget
+    pub max_total_emission: u64, // Cap on everything the schedule mints
+}
+
+impl EmissionParams {
+    pub fn space() -> usize {
+        8 + // Start
+            8 + // Epoch duration
+            8 + // Initial budget
+            8 + // Halving interval
+            8 // Max total emission
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.epoch_duration > 0 && self.initial_budget > 0 && self.max_total_emission > 0,
+            ErrorCode::InvalidEmissionParams
+        );
+        Ok(())
+    }
+
+    /// Number of epochs that have started by `now`
+    pub fn epochs_started(&self, now: i64) -> u64 {
+        if now < self.start_ts {
+            return 0;
+        }
+        ((now - self.start_ts) / self.epoch_duration) as u64 + 1
+    }
+
+    pub fn budget(&self, epoch: u64) -> u64 {
+        if self.halving_interval == 0 {
+            return self.initial_budget;
+        }
+        let halvings = epoch / self.halving_interval;
+        if halvings >= 64 {
+            0
+        } else {
+            self.initial_budget >> halvings
+        }
+    }
+}
+
+#[account]
+pub struct EmissionSchedule {
+    pub mint: Pubkey,           // Mint being emitted
+    pub rewards_vault: Pubkey,  // Token account receiving emissions
+    pub params: EmissionParams, // Emission curve
+    pub next_epoch: u64,        // Next epoch to emit
+    pub emitted: u64,           // Tokens emitted so far
+    pub bump: u8,               // PDA bump
+}
+
+impl EmissionSchedule {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Mint
+            32 + // Rewards vault
+            EmissionParams::space() + // Params
+            8 + // Next epoch
+            8 + // Emitted
+            1 // Bump
+    }
+}
+
+/// Share of treasury fee revenue burned each epoch
+#[account]
+pub struct BurnPolicy {
+    pub mint: Pubkey,           // Mint being burned
+    pub treasury: Pubkey,       // Treasury token account owned by the treasury PDA
+    pub burn_bps: u16,          // Share of fee revenue burned
+    pub start_ts: i64,          // Start of epoch 0
+    pub epoch_duration: i64,    // Seconds per epoch
+    pub next_epoch: u64,        // Earliest epoch the next burn may run in
+    pub last_treasury_balance: u64, // Treasury balance after the previous burn
+    pub total_fee_revenue: u64, // Fee revenue seen across all burns
+    pub bump: u8,               // PDA bump
+}
+
+impl BurnPolicy {
+    pub const MAX_BPS: u16 = 10_000;
+
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Mint
+            32 + // Treasury
+            2 + // Burn bps
+            8 + // Start
+            8 + // Epoch duration
+            8 + // Next epoch
+            8 + // Last treasury balance
+            8 + // Total fee revenue
+            1 // Bump
+    }
+
+    pub fn validate(burn_bps: u16, epoch_duration: i64) -> Result<()> {
+        require!(
+            burn_bps <= Self::MAX_BPS && epoch_duration > 0,
+            ErrorCode::InvalidBurnPolicy
+        );
+        Ok(())
+    }
+
+    pub fn current_epoch(&self, now: i64) -> u64 {
+        ((now - self.start_ts).max(0) / self.epoch_duration) as u64
+    }
+
+    /// Switch to a new epoch length, restarting the epoch count at `now`
+    ///
+    /// A burn still due under the old length stays due, and one that
+    /// already ran this epoch waits out a full new epoch, so a change can
+    /// neither grant an extra burn nor stall burning.
+    pub fn set_epoch_duration(&mut self, epoch_duration: i64, now: i64) {
+        if epoch_duration == self.epoch_duration {
+            return;
+        }
+        let burn_due = self.current_epoch(now) >= self.next_epoch;
+        self.start_ts = now;
+        self.epoch_duration = epoch_duration;
+        self.next_epoch = if burn_due { 0 } else { 1 };
+    }
+}
+
+/// Transfer-fee extension settings for a Token-2022 mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct TransferFeeParams {
+    pub fee_bps: u16,           // Fee per transfer in basis points
+    pub max_fee: u64,           // Fee cap per transfer in base units
+}
+
+impl TransferFeeParams {
+    pub const MAX_FEE_BPS: u16 = 10_000;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.fee_bps <= Self::MAX_FEE_BPS, ErrorCode::InvalidTransferFee);
+        Ok(())
+    }
+}
+
+/// Admin change that has to wait out the timelock
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum AdminAction {
+    SetFreezeState { is_frozen: bool },
+    TransferAuthority { new_authority: Pubkey },
+    UpdateTimelock { guardian: Pubkey, delay: i64 },
+    WithdrawTreasury { treasury: Pubkey, destination: Pubkey, amount: u64 },
+    InitializeEmission { rewards_vault: Pubkey, params: EmissionParams },
+    SetBurnPolicy { burn_bps: u16, epoch_duration: i64 },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ActionStatus {
+    Queued,
+    Cancelled,
+    Executed,
+}
+
+#[account]
+pub struct QueuedAction {
+    pub timelock: Pubkey,       // Timelock the action was queued on
+    pub id: u64,                // Sequential action id
+    pub action: AdminAction,    // Change to apply
+    pub eta: i64,               // Earliest execution time
+    pub status: ActionStatus,   // Lifecycle state
+    pub queued_by: Pubkey,      // Authority that queued it
+    pub authority_nonce: u64,   // Timelock authority nonce when queued
+    pub bump: u8,               // PDA bump
+}
+
+impl TokenInfo {
+    pub const MAX_NAME_LEN: usize = 50;
+    pub const MAX_SYMBOL_LEN: usize = 10;
+    pub const MAX_URI_LEN: usize = 200;
+    pub const MAX_METAPLEX_NAME_LEN: usize = 32;
+
+    pub fn space() -> usize {
+        // Fixed size fields
+        let fixed_size = 8 + // Discriminator
+            1 + // Decimals
+            32 + // Authority pubkey
+            32 + // Mint pubkey
+            8 + // Created timestamp
+            8 + // Total supply
+            8 + // Circulating supply
+            1 + // Is frozen
+            1 + 32 + // Timelock
+            8 + // Total emitted
+            8 + // Total burned
+            8; // Burned by protocol
+        
+        // Variable size fields with maximum lengths
+        let variable_size = 4 + Self::MAX_NAME_LEN + // name (String with max 50 chars)
+            4 + Self::MAX_SYMBOL_LEN +                // symbol (String with max 10 chars)
+            4 + Self::MAX_URI_LEN;                    // uri (String with max 200 chars)
+            
+        fixed_size + variable_size
+    }
+    
+    /// Minted tokens count towards both total and circulating supply
+    pub fn record_mint(&mut self, amount: u64) -> Result<()> {
+        self.total_supply = self.total_supply.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        self.circulating_supply = self.circulating_supply.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        Ok(())
+    }
+    
+    /// Burned tokens leave circulation; `circulating_supply` always equals
+    /// `total_supply - total_burned`
+    pub fn record_burn(&mut self, amount: u64) -> Result<()> {
+        self.circulating_supply = self.circulating_supply.checked_sub(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        self.total_burned = self.total_burned.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        Ok(())
+    }
+    
+    /// Gate for admin actions run by their own instruction: open to the
+    /// authority while no timelock is set, otherwise the matching queued
+    /// action is consumed once its delay has passed
+    pub fn authorize_admin_action(
+        &self,
+        timelock: Option<&Account<Timelock>>,
+        queued: Option<&mut Account<QueuedAction>>,
+        action: &AdminAction,
+        now: i64,
+    ) -> Result<()> {
+        let timelock_key = match self.timelock {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+        let (timelock, queued) = match (timelock, queued) {
+            (Some(timelock), Some(queued)) => (timelock, queued),
+            _ => return err!(ErrorCode::TimelockRequired),
+        };
+        require!(
+            timelock.key() == timelock_key && queued.timelock == timelock_key,
+            ErrorCode::TimelockRequired
+        );
+        require!(queued.status == ActionStatus::Queued, ErrorCode::ActionNotQueued);
+        require!(now >= queued.eta, ErrorCode::TimelockNotElapsed);
+        require!(
+            queued.authority_nonce == timelock.authority_nonce,
+            ErrorCode::StaleAdminAction
+        );
+        require!(queued.action == *action, ErrorCode::AdminActionMismatch);
+        
+        queued.status = ActionStatus::Executed;
+        
+        emit!(AdminActionExecutedEvent {
+            mint: self.mint,
+            id: queued.id,
+            action: queued.action.clone(),
+        });
+        
+        Ok(())
+    }
+    
+    /// Record the difference between `circulating_supply` and the mint's
+    /// actual supply as a mint or burn; returns `(minted, burned)`
+    pub fn reconcile_supply(&mut self, actual_supply: u64) -> Result<(u64, u64)> {
+        let minted = actual_supply.saturating_sub(self.circulating_supply);
+        let burned = self.circulating_supply.saturating_sub(actual_supply);
+        self.record_mint(minted)?;
+        self.record_burn(burned)?;
+        Ok((minted, burned))
+    }
+    
+    pub fn validate_metadata(name: &str, symbol: &str, uri: &str) -> Result<()> {
+        require!(
+            name.len() <= Self::MAX_NAME_LEN
+                && symbol.len() <= Self::MAX_SYMBOL_LEN
+                && uri.len() <= Self::MAX_URI_LEN,
+            ErrorCode::MetadataTooLong
+        );
+        Ok(())
+    }
+    
+    fn metaplex_data(name: &str, symbol: &str, uri: &str) -> DataV2 {
+        DataV2 {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            uri: uri.to_string(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        }
+    }
+}
+
+impl Timelock {
+    pub const MAX_DELAY: i64 = 30 * 24 * 60 * 60;
+
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Mint
+            32 + // Guardian
+            8 + // Delay
+            8 + // Next action id
+            8 + // Authority nonce
+            1 // Bump
+    }
+
+    pub fn validate_delay(delay: i64) -> Result<()> {
+        require!(delay > 0 && delay <= Self::MAX_DELAY, ErrorCode::InvalidTimelockDelay);
+        Ok(())
+    }
+}
+
+impl QueuedAction {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Timelock
+            8 + // Id
+            1 + 32 + 32 + 8 + // Action (largest variant)
+            8 + // ETA
+            1 + // Status
+            32 + // Queued by
+            8 + // Authority nonce
+            1 // Bump
     }
 }
 
@@ -311,6 +1917,48 @@ pub enum ErrorCode {
     TokenFrozen,
     #[msg("Numeric overflow occurred")]
     NumericOverflow,
+    #[msg("This change must be queued through the timelock")]
+    TimelockRequired,
+    #[msg("Timelock is already enabled")]
+    TimelockAlreadyEnabled,
+    #[msg("Timelock delay must be positive and at most 30 days")]
+    InvalidTimelockDelay,
+    #[msg("Action is not queued")]
+    ActionNotQueued,
+    #[msg("Timelock has not elapsed")]
+    TimelockNotElapsed,
+    #[msg("Token extensions require the Token-2022 program")]
+    Token2022Required,
+    #[msg("Transfer fee must be at most 10000 basis points")]
+    InvalidTransferFee,
+    #[msg("Mint has no transfer-fee extension")]
+    TransferFeeNotEnabled,
+    #[msg("Treasury must be a token account of this mint owned by the treasury PDA")]
+    InvalidTreasury,
+    #[msg("Name, symbol or uri is too long")]
+    MetadataTooLong,
+    #[msg("Classic SPL mints need the metadata account and token metadata program")]
+    MetadataAccountRequired,
+    #[msg("Emission schedule needs a positive epoch duration, budget and cap")]
+    InvalidEmissionParams,
+    #[msg("Rewards vault must be a token account of this mint")]
+    InvalidRewardsVault,
+    #[msg("Next emission epoch has not started")]
+    EpochNotReached,
+    #[msg("Emission schedule is exhausted")]
+    EmissionExhausted,
+    #[msg("Burn share must be at most 10000 basis points with a positive epoch")]
+    InvalidBurnPolicy,
+    #[msg("Action was queued by a previous authority")]
+    StaleAdminAction,
+    #[msg("This action runs through its own instruction")]
+    ActionRequiresInstruction,
+    #[msg("Queued action does not match this instruction")]
+    AdminActionMismatch,
+    #[msg("Fee revenue since the last burn can't leave the treasury")]
+    UnburnedFeeRevenue,
+    #[msg("Emissions are enabled; new supply comes from the schedule")]
+    EmissionScheduleActive,
 }
 
 // Events
@@ -323,6 +1971,14 @@ pub struct TokenInitializedEvent {
     pub decimals: u8,
 }
 
+#[event]
+pub struct TokenMetadataUpdatedEvent {
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
 #[event]
 pub struct TokensMintedEvent {
     pub mint: Pubkey,
@@ -339,6 +1995,15 @@ pub struct TokensBurnedEvent {
     pub circulating_supply: u64,
 }
 
+#[event]
+pub struct SupplySyncedEvent {
+    pub mint: Pubkey,
+    pub recorded_supply: u64,
+    pub actual_supply: u64,
+    pub minted_outside: u64,
+    pub burned_outside: u64,
+}
+
 #[event]
 pub struct TokenFreezeStateChangedEvent {
     pub mint: Pubkey,
@@ -350,4 +2015,252 @@ pub struct AuthorityTransferredEvent {
     pub mint: Pubkey,
     pub old_authority: Pubkey,
     pub new_authority: Pubkey,
-} 
\ No newline at end of file
+} 
+
+#[event]
+pub struct TimelockUpdatedEvent {
+    pub mint: Pubkey,
+    pub guardian: Pubkey,
+    pub delay: i64,
+}
+
+#[event]
+pub struct AdminActionQueuedEvent {
+    pub mint: Pubkey,
+    pub id: u64,
+    pub action: AdminAction,
+    pub eta: i64,
+}
+
+#[event]
+pub struct AdminActionCancelledEvent {
+    pub mint: Pubkey,
+    pub id: u64,
+    pub cancelled_by: Pubkey,
+}
+
+#[event]
+pub struct AdminActionExecutedEvent {
+    pub mint: Pubkey,
+    pub id: u64,
+    pub action: AdminAction,
+}
+
+#[event]
+pub struct EmissionScheduleCreatedEvent {
+    pub mint: Pubkey,
+    pub rewards_vault: Pubkey,
+    pub params: EmissionParams,
+}
+
+#[event]
+pub struct EmissionCrankedEvent {
+    pub mint: Pubkey,
+    pub epoch: u64,
+    pub amount: u64,
+    pub total_emitted: u64,
+    pub circulating_supply: u64,
+}
+
+#[event]
+pub struct BurnPolicyUpdatedEvent {
+    pub mint: Pubkey,
+    pub treasury: Pubkey,
+    pub burn_bps: u16,
+    pub epoch_duration: i64,
+}
+
+#[event]
+pub struct ProtocolBurnEvent {
+    pub mint: Pubkey,
+    pub epoch: u64,
+    pub fee_revenue: u64,
+    pub burned: u64,
+    pub burned_by_protocol: u64,
+    pub circulating_supply: u64,
+}
+
+#[event]
+pub struct TransferFeesHarvestedEvent {
+    pub mint: Pubkey,
+    pub treasury: Pubkey,
+    pub amount: u64,
+    pub source_count: u16,
+}
+
+#[event]
+pub struct TreasuryWithdrawnEvent {
+    pub mint: Pubkey,
+    pub treasury: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// xorshift64, so sequences are random but reproducible from the seed
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: u64) -> u64 {
+            if bound == 0 {
+                0
+            } else {
+                self.next() % bound
+            }
+        }
+    }
+
+    fn token_info() -> TokenInfo {
+        TokenInfo {
+            name: "Wale".to_string(),
+            symbol: "WALE".to_string(),
+            uri: String::new(),
+            decimals: 9,
+            authority: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            created_at: 0,
+            total_supply: 0,
+            circulating_supply: 0,
+            is_frozen: false,
+            timelock: None,
+            total_emitted: 0,
+            total_burned: 0,
+            burned_by_protocol: 0,
+        }
+    }
+
+    fn supply(info: &TokenInfo) -> (u64, u64, u64) {
+        (info.total_supply, info.circulating_supply, info.total_burned)
+    }
+
+    fn assert_invariant(info: &TokenInfo) {
+        assert_eq!(info.circulating_supply, info.total_supply - info.total_burned);
+    }
+
+    #[test]
+    fn random_mint_and_burn_sequences_keep_supply_consistent() {
+        for seed in 1..=200u64 {
+            let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+            let mut info = token_info();
+            // Shadow ledger, tracked independently of `TokenInfo`
+            let (mut minted, mut burned) = (0u128, 0u128);
+
+            for _ in 0..500 {
+                let before = supply(&info);
+                match rng.below(4) {
+                    0 | 1 => {
+                        let amount = rng.below(1 << 40);
+                        info.record_mint(amount).unwrap();
+                        minted += u128::from(amount);
+                    }
+                    2 => {
+                        let amount = rng.below(info.circulating_supply + 1);
+                        info.record_burn(amount).unwrap();
+                        burned += u128::from(amount);
+                    }
+                    _ => {
+                        // Burning more than circulates must fail untouched
+                        let excess = info.circulating_supply + 1 + rng.below(1 << 20);
+                        assert!(info.record_burn(excess).is_err());
+                        assert_eq!(supply(&info), before);
+                    }
+                }
+                assert_invariant(&info);
+                assert_eq!(u128::from(info.total_supply), minted);
+                assert_eq!(u128::from(info.total_burned), burned);
+            }
+        }
+    }
+
+    #[test]
+    fn overflowing_mint_fails_untouched() {
+        let mut info = token_info();
+        info.record_mint(u64::MAX - 10).unwrap();
+        info.record_burn(100).unwrap();
+
+        let before = supply(&info);
+        assert!(info.record_mint(11).is_err());
+        assert_eq!(supply(&info), before);
+        assert_invariant(&info);
+    }
+
+    #[test]
+    fn reconcile_matches_any_actual_supply() {
+        for seed in 1..=200u64 {
+            let mut rng = Rng(seed.wrapping_mul(0xd1b5_4a32_d192_ed03));
+            let mut info = token_info();
+
+            for _ in 0..200 {
+                if rng.below(2) == 0 {
+                    info.record_mint(rng.below(1 << 30)).unwrap();
+                } else {
+                    info.record_burn(rng.below(info.circulating_supply + 1)).unwrap();
+                }
+
+                // Supply changed outside the program in either direction
+                let actual = rng.below(info.circulating_supply * 2 + 1);
+                let before = supply(&info);
+                let (minted, burned) = info.reconcile_supply(actual).unwrap();
+
+                assert!(minted == 0 || burned == 0);
+                assert_eq!(info.circulating_supply, actual);
+                assert_eq!(info.total_supply, before.0 + minted);
+                assert_eq!(info.total_burned, before.2 + burned);
+                assert_invariant(&info);
+            }
+        }
+    }
+
+    fn burn_policy(start_ts: i64, epoch_duration: i64, next_epoch: u64) -> BurnPolicy {
+        BurnPolicy {
+            mint: Pubkey::new_unique(),
+            treasury: Pubkey::new_unique(),
+            burn_bps: 5_000,
+            start_ts,
+            epoch_duration,
+            next_epoch,
+            last_treasury_balance: 0,
+            total_fee_revenue: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn shorter_epoch_does_not_grant_an_extra_burn() {
+        // Burned in epoch 9 of 100s epochs; the next burn is due at 1000
+        let mut policy = burn_policy(0, 100, 10);
+        policy.set_epoch_duration(10, 950);
+
+        assert!(policy.current_epoch(950) < policy.next_epoch);
+        assert!(policy.current_epoch(959) < policy.next_epoch);
+        assert!(policy.current_epoch(960) >= policy.next_epoch);
+    }
+
+    #[test]
+    fn longer_epoch_keeps_a_due_burn_due() {
+        // Epoch 10 of 100s epochs has started and its burn hasn't run
+        let mut policy = burn_policy(0, 100, 10);
+        policy.set_epoch_duration(10_000, 1_050);
+
+        assert!(policy.current_epoch(1_050) >= policy.next_epoch);
+    }
+
+    #[test]
+    fn unchanged_epoch_keeps_the_schedule() {
+        let mut policy = burn_policy(0, 100, 10);
+        policy.set_epoch_duration(100, 950);
+
+        assert_eq!(policy.start_ts, 0);
+        assert_eq!(policy.next_epoch, 10);
+    }
+}
@@ -0,0 +1,286 @@
+//! Off-chain Merkle tree builder for the distributor program
+//!
+//! Reads a CSV of `recipient,amount` rows (amounts in base units, an
+//! optional header row is skipped), assigns each row its position as the
+//! claim index and writes the root plus every recipient's proof as JSON.
+//! Leaves and inner nodes are hashed with `distributor::leaf_hash` and
+//! `distributor::node_hash`, so the output always matches on-chain
+//! verification.
+//!
+//! Usage: `merkle_builder <claims.csv> <output.json>`
+
+use distributor::{leaf_hash, node_hash, verify_proof, MAX_PROOF_LEN};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::process;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum BuildError {
+    Io(String),
+    Parse { line: usize, message: String },
+    DuplicateRecipient { line: usize, recipient: Pubkey },
+    Empty,
+    Overflow,
+    TooDeep,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Io(e) => write!(f, "io error: {}", e),
+            BuildError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            BuildError::DuplicateRecipient { line, recipient } => {
+                write!(f, "line {}: duplicate recipient {}", line, recipient)
+            }
+            BuildError::Empty => write!(f, "no claims in input"),
+            BuildError::Overflow => write!(f, "total amount overflows u64"),
+            BuildError::TooDeep => write!(f, "too many claims for the on-chain proof limit"),
+        }
+    }
+}
+
+/// One row of the distribution
+pub struct Entry {
+    pub index: u64,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+/// A built tree; `levels[0]` holds the leaves and the last level the root
+pub struct Tree {
+    pub levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl Tree {
+    pub fn build(entries: &[Entry]) -> Result<Tree, BuildError> {
+        if entries.is_empty() {
+            return Err(BuildError::Empty);
+        }
+
+        let leaves = entries
+            .iter()
+            .map(|entry| leaf_hash(entry.index, &entry.recipient, entry.amount))
+            .collect();
+        let mut levels: Vec<Vec<[u8; 32]>> = vec![leaves];
+
+        while levels.last().unwrap().len() > 1 {
+            // An unpaired last node moves up unchanged
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => node_hash(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+
+        if levels.len() - 1 > MAX_PROOF_LEN {
+            return Err(BuildError::TooDeep);
+        }
+        Ok(Tree { levels })
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().unwrap()[0]
+    }
+
+    /// Siblings from the leaf up, skipping levels where the node is unpaired
+    pub fn proof(&self, index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = position ^ 1;
+            if sibling < level.len() {
+                proof.push(level[sibling]);
+            }
+            position /= 2;
+        }
+        proof
+    }
+}
+
+/// Parse `recipient,amount` rows; the first row is skipped if it doesn't
+/// start with a valid pubkey
+pub fn parse_csv(input: &str) -> Result<Vec<Entry>, BuildError> {
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+
+    for (i, raw) in input.lines().enumerate() {
+        let line = i + 1;
+        let row = raw.trim();
+        if row.is_empty() {
+            continue;
+        }
+
+        let mut fields = row.split(',').map(str::trim);
+        let recipient = fields.next().unwrap_or_default();
+        let amount = fields.next().unwrap_or_default();
+        if fields.next().is_some() {
+            return Err(BuildError::Parse { line, message: "expected 2 columns".to_string() });
+        }
+
+        let recipient = match Pubkey::from_str(recipient) {
+            Ok(recipient) => recipient,
+            Err(_) if line == 1 => continue,
+            Err(e) => {
+                return Err(BuildError::Parse { line, message: format!("invalid recipient: {}", e) })
+            }
+        };
+        let amount = u64::from_str(amount).map_err(|e| BuildError::Parse {
+            line,
+            message: format!("invalid amount: {}", e),
+        })?;
+        if amount == 0 {
+            return Err(BuildError::Parse { line, message: "amount must be positive".to_string() });
+        }
+        if !seen.insert(recipient) {
+            return Err(BuildError::DuplicateRecipient { line, recipient });
+        }
+
+        entries.push(Entry { index: entries.len() as u64, recipient, amount });
+    }
+
+    Ok(entries)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Render the distribution as JSON, checking every proof on the way
+pub fn render(entries: &[Entry], tree: &Tree) -> Result<String, BuildError> {
+    let root = tree.root();
+    let total = entries
+        .iter()
+        .try_fold(0u64, |sum, entry| sum.checked_add(entry.amount))
+        .ok_or(BuildError::Overflow)?;
+
+    let mut claims = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let proof = tree.proof(entry.index as usize);
+        assert!(verify_proof(&proof, root, leaf_hash(entry.index, &entry.recipient, entry.amount)));
+
+        let proof: Vec<String> = proof.iter().map(|node| format!("\"{}\"", to_hex(node))).collect();
+        claims.push(format!(
+            "    {{\"index\": {}, \"recipient\": \"{}\", \"amount\": \"{}\", \"proof\": [{}]}}",
+            entry.index,
+            entry.recipient,
+            entry.amount,
+            proof.join(", "),
+        ));
+    }
+
+    Ok(format!(
+        "{{\n  \"merkle_root\": \"{}\",\n  \"max_total_claim\": \"{}\",\n  \"max_num_nodes\": {},\n  \"claims\": [\n{}\n  ]\n}}\n",
+        to_hex(&root),
+        total,
+        entries.len(),
+        claims.join(",\n"),
+    ))
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 3 {
+        eprintln!("usage: {} <claims.csv> <output.json>", args[0]);
+        process::exit(2);
+    }
+
+    let result = fs::read_to_string(&args[1])
+        .map_err(|e| BuildError::Io(e.to_string()))
+        .and_then(|input| parse_csv(&input))
+        .and_then(|entries| {
+            let tree = Tree::build(&entries)?;
+            let json = render(&entries, &tree)?;
+            fs::write(&args[2], json).map_err(|e| BuildError::Io(e.to_string()))?;
+            Ok((entries.len(), tree.root()))
+        });
+
+    match result {
+        Ok((count, root)) => {
+            println!("OK {} claims, root {}", count, to_hex(&root));
+        }
+        Err(e) => {
+            eprintln!("FAILED {}: {}", args[1], e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(count: usize) -> Vec<Entry> {
+        (0..count)
+            .map(|i| Entry {
+                index: i as u64,
+                recipient: Pubkey::new_unique(),
+                amount: 1_000 + i as u64,
+            })
+            .collect()
+    }
+
+    fn leaf(entry: &Entry) -> [u8; 32] {
+        leaf_hash(entry.index, &entry.recipient, entry.amount)
+    }
+
+    #[test]
+    fn every_proof_round_trips() {
+        // Single leaf, even and odd sizes, and 2^k + 1 where the last leaf
+        // moves up unpaired through every level
+        for count in [1, 2, 3, 4, 5, 7, 9, 17, 33, 65] {
+            let entries = entries(count);
+            let tree = Tree::build(&entries).unwrap();
+            let root = tree.root();
+            for entry in &entries {
+                let proof = tree.proof(entry.index as usize);
+                assert!(verify_proof(&proof, root, leaf(entry)), "size {} index {}", count, entry.index);
+            }
+        }
+    }
+
+    #[test]
+    fn single_leaf_is_the_root() {
+        let entries = entries(1);
+        let tree = Tree::build(&entries).unwrap();
+        assert_eq!(tree.root(), leaf(&entries[0]));
+        assert!(tree.proof(0).is_empty());
+    }
+
+    #[test]
+    fn unpaired_nodes_shorten_the_proof() {
+        // With 2^k + 1 leaves the last one has no sibling until the top level
+        let entries = entries(9);
+        let tree = Tree::build(&entries).unwrap();
+        assert_eq!(tree.levels.len(), 5);
+        assert_eq!(tree.proof(0).len(), 4);
+        assert_eq!(tree.proof(8), vec![tree.levels[3][0]]);
+    }
+
+    #[test]
+    fn proofs_reject_tampered_claims() {
+        let entries = entries(5);
+        let tree = Tree::build(&entries).unwrap();
+        let root = tree.root();
+        let entry = &entries[2];
+        let proof = tree.proof(2);
+        
+        assert!(!verify_proof(&proof, root, leaf_hash(entry.index, &entry.recipient, entry.amount + 1)));
+        assert!(!verify_proof(&proof, root, leaf_hash(3, &entry.recipient, entry.amount)));
+        assert!(!verify_proof(&proof, root, leaf_hash(entry.index, &Pubkey::new_unique(), entry.amount)));
+        assert!(!verify_proof(&tree.proof(1), root, leaf(entry)));
+    }
+
+    #[test]
+    fn build_rejects_empty_input() {
+        assert!(matches!(Tree::build(&[]), Err(BuildError::Empty)));
+    }
+}
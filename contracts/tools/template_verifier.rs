@@ -0,0 +1,158 @@
+//! Off-chain template content verifier
+//!
+//! Loads a `TemplateAccount` from the cluster, resolves its `ContentRef`
+//! against a local mirror directory and checks the body's SHA-256 against
+//! the digest recorded on-chain.
+//!
+//! Mirror layout, keyed by the lowercase hex digest:
+//!
+//! ```text
+//! <mirror>/ipfs/<sha256>
+//! <mirror>/arweave/<sha256>
+//! <mirror>/http/<sha256>
+//! ```
+//!
+//! Usage: `template_verifier <rpc-url> <template-pubkey> <mirror-dir>`
+
+use anchor_lang::AccountDeserialize;
+use mcp::{ContentRef, TemplateAccount};
+use sha2::{Digest, Sha256};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum VerifyError {
+    Rpc(String),
+    Decode(String),
+    MissingBody(PathBuf),
+    DigestMismatch { expected: String, actual: String },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Rpc(e) => write!(f, "rpc error: {}", e),
+            VerifyError::Decode(e) => write!(f, "failed to decode template account: {}", e),
+            VerifyError::MissingBody(path) => {
+                write!(f, "template body not found in mirror: {}", path.display())
+            }
+            VerifyError::DigestMismatch { expected, actual } => {
+                write!(f, "digest mismatch: on-chain {} but mirror has {}", expected, actual)
+            }
+        }
+    }
+}
+
+/// Result of a successful verification
+pub struct Verified {
+    pub template_id: String,
+    pub scheme: &'static str,
+    pub sha256: Option<String>,
+    pub body_len: usize,
+}
+
+/// Fetch and decode a template account
+pub fn fetch_template(rpc: &RpcClient, address: &Pubkey) -> Result<TemplateAccount, VerifyError> {
+    let data = rpc
+        .get_account_data(address)
+        .map_err(|e| VerifyError::Rpc(e.to_string()))?;
+    TemplateAccount::try_deserialize(&mut data.as_slice())
+        .map_err(|e| VerifyError::Decode(e.to_string()))
+}
+
+/// Resolve the body behind a template's `ContentRef` and check its digest
+pub fn verify_content(
+    template: &TemplateAccount,
+    mirror: &Path,
+) -> Result<Verified, VerifyError> {
+    let content = &template.metadata;
+
+    let expected = match content.sha256() {
+        Some(digest) => digest,
+        None => {
+            let body_len = match content {
+                ContentRef::Inline { data } => data.len(),
+                _ => 0,
+            };
+            return Ok(Verified {
+                template_id: template.template_id.clone(),
+                scheme: scheme(content),
+                sha256: None,
+                body_len,
+            });
+        }
+    };
+
+    let expected_hex = to_hex(&expected);
+    let path = mirror.join(scheme(content)).join(&expected_hex);
+    let body = fs::read(&path).map_err(|_| VerifyError::MissingBody(path.clone()))?;
+
+    let actual = Sha256::digest(&body);
+    if actual.as_slice() != expected {
+        return Err(VerifyError::DigestMismatch {
+            expected: expected_hex,
+            actual: to_hex(&actual),
+        });
+    }
+
+    Ok(Verified {
+        template_id: template.template_id.clone(),
+        scheme: scheme(content),
+        sha256: Some(expected_hex),
+        body_len: body.len(),
+    })
+}
+
+fn scheme(content: &ContentRef) -> &'static str {
+    match content {
+        ContentRef::Ipfs { .. } => "ipfs",
+        ContentRef::Arweave { .. } => "arweave",
+        ContentRef::Inline { .. } => "inline",
+        ContentRef::Http { .. } => "http",
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 4 {
+        eprintln!("usage: {} <rpc-url> <template-pubkey> <mirror-dir>", args[0]);
+        process::exit(2);
+    }
+
+    let rpc = RpcClient::new(args[1].clone());
+    let address = match Pubkey::from_str(&args[2]) {
+        Ok(address) => address,
+        Err(e) => {
+            eprintln!("invalid template pubkey: {}", e);
+            process::exit(2);
+        }
+    };
+
+    let result = fetch_template(&rpc, &address)
+        .and_then(|template| verify_content(&template, Path::new(&args[3])));
+
+    match result {
+        Ok(verified) => {
+            println!(
+                "OK {} ({}, {} bytes, sha256 {})",
+                verified.template_id,
+                verified.scheme,
+                verified.body_len,
+                verified.sha256.as_deref().unwrap_or("inline"),
+            );
+        }
+        Err(e) => {
+            eprintln!("FAILED {}: {}", address, e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! Checked token transfers for programs moving WALE
+//!
+//! The WALE mint may carry a transfer hook (see `transfer_hook`), and
+//! anchor-spl's `token_interface::transfer_checked` drops the remaining
+//! accounts the hook needs, so every transfer of a hooked mint through it
+//! fails. Programs use `transfer_checked` here instead.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TransferChecked;
+
+/// Drop-in for `token_interface::transfer_checked` that forwards
+/// `ctx.remaining_accounts` to the mint's transfer hook
+///
+/// When the mint has a transfer hook, the remaining accounts must include
+/// the hook program, its `["extra-account-metas", mint]` validation account
+/// and every account that list resolves to. For the WALE hook those are,
+/// all derived under the hook program:
+///
+/// - `["hook_config", mint]`
+/// - `["deny", mint, source_owner]`
+/// - `["deny", mint, destination_owner]`
+/// - `["service", mint, destination_owner]`
+///
+/// The deny and service entries are passed whether or not they exist.
+/// Accounts are looked up by key, so callers can forward their whole
+/// `remaining_accounts` even when it holds accounts for other purposes.
+/// Mints without a hook need nothing extra.
+pub fn transfer_checked<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    spl_token_2022::onchain::invoke_transfer_checked(
+        ctx.program.key,
+        ctx.accounts.from,
+        ctx.accounts.mint,
+        ctx.accounts.to,
+        ctx.accounts.authority,
+        &ctx.remaining_accounts,
+        amount,
+        decimals,
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
@@ -0,0 +1,577 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use spl_token_2022::extension::transfer_hook as hook_extension;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_tlv_account_resolution::account::ExtraAccountMeta;
+use spl_tlv_account_resolution::seeds::Seed;
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_transfer_hook_interface::instruction::{ExecuteInstruction, TransferHookInstruction};
+
+declare_id!("WALEHook11111111111111111111111111111111111");
+
+#[program]
+pub mod transfer_hook {
+    use super::*;
+
+    /// Set up the hook for a Token-2022 mint
+    ///
+    /// The mint must have been created pointing its transfer-hook extension
+    /// here, e.g. via `transfer_hook_program` in `wale_token::initialize_token`,
+    /// and this must be signed by the mint's transfer-hook authority. `authority`
+    /// manages the deny list and service mode afterwards and is meant to
+    /// be the governance realm's authority PDA.
+    pub fn initialize_hook(
+        ctx: Context<InitializeHook>,
+        authority: Pubkey,
+        service_only: bool,
+    ) -> Result<()> {
+        let mint_info = ctx.accounts.mint.to_account_info();
+        {
+            let data = mint_info.try_borrow_data()?;
+            let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+            let hook = mint.get_extension::<hook_extension::TransferHook>()
+                .map_err(|_| error!(ErrorCode::HookNotConfigured))?;
+            require!(
+                Option::<Pubkey>::from(hook.program_id) == Some(crate::ID),
+                ErrorCode::HookNotConfigured
+            );
+            require!(
+                Option::<Pubkey>::from(hook.authority) == Some(ctx.accounts.hook_authority.key()),
+                ErrorCode::Unauthorized
+            );
+        }
+
+        let metas = extra_account_metas()?;
+        let size = ExtraAccountMetaList::size_of(metas.len())?;
+        let mint_key = mint_info.key();
+        let seeds = &[
+            b"extra-account-metas".as_ref(),
+            mint_key.as_ref(),
+            &[*ctx.bumps.get("extra_account_meta_list").unwrap()],
+        ];
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.extra_account_meta_list.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            Rent::get()?.minimum_balance(size),
+            size as u64,
+            &crate::ID,
+        )?;
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &metas,
+        )?;
+
+        let config = &mut ctx.accounts.hook_config;
+        config.mint = mint_key;
+        config.authority = authority;
+        config.service_only = service_only;
+        config.deny_count = 0;
+        config.bump = *ctx.bumps.get("hook_config").unwrap();
+
+        emit!(HookConfigUpdatedEvent {
+            mint: config.mint,
+            authority,
+            service_only,
+        });
+
+        Ok(())
+    }
+
+    /// Change the hook authority or toggle service-only mode
+    pub fn update_hook_config(
+        ctx: Context<UpdateHookConfig>,
+        new_authority: Option<Pubkey>,
+        service_only: Option<bool>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.hook_config;
+
+        if let Some(authority) = new_authority {
+            config.authority = authority;
+        }
+
+        if let Some(service_only) = service_only {
+            config.service_only = service_only;
+        }
+
+        emit!(HookConfigUpdatedEvent {
+            mint: config.mint,
+            authority: config.authority,
+            service_only: config.service_only,
+        });
+
+        Ok(())
+    }
+
+    /// Block `wallet` from sending or receiving the mint
+    pub fn deny_wallet(
+        ctx: Context<DenyWallet>,
+        wallet: Pubkey,
+        reason_hash: [u8; 32],
+    ) -> Result<()> {
+        let entry = &mut ctx.accounts.deny_entry;
+        entry.mint = ctx.accounts.hook_config.mint;
+        entry.wallet = wallet;
+        entry.reason_hash = reason_hash;
+        entry.denied_at = Clock::get()?.unix_timestamp;
+        entry.bump = *ctx.bumps.get("deny_entry").unwrap();
+
+        let config = &mut ctx.accounts.hook_config;
+        config.deny_count = config.deny_count.checked_add(1)
+            .ok_or(ErrorCode::NumericOverflow)?;
+
+        emit!(DenyListChangedEvent {
+            mint: entry.mint,
+            wallet,
+            denied: true,
+            timestamp: entry.denied_at,
+        });
+
+        Ok(())
+    }
+
+    /// Lift a wallet's block
+    pub fn allow_wallet(
+        ctx: Context<AllowWallet>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.hook_config;
+        config.deny_count = config.deny_count.saturating_sub(1);
+
+        emit!(DenyListChangedEvent {
+            mint: config.mint,
+            wallet: ctx.accounts.deny_entry.wallet,
+            denied: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Allow transfers into token accounts owned by `owner` in service-only
+    /// mode, e.g. an `mcp` bounty or listing escrow PDA
+    pub fn register_service_account(
+        ctx: Context<RegisterServiceAccount>,
+        owner: Pubkey,
+    ) -> Result<()> {
+        let service = &mut ctx.accounts.service_account;
+        service.mint = ctx.accounts.hook_config.mint;
+        service.owner = owner;
+        service.bump = *ctx.bumps.get("service_account").unwrap();
+
+        emit!(ServiceAccountChangedEvent {
+            mint: service.mint,
+            owner,
+            registered: true,
+        });
+
+        Ok(())
+    }
+
+    /// Stop allowing transfers into a service account
+    pub fn remove_service_account(
+        ctx: Context<RemoveServiceAccount>,
+    ) -> Result<()> {
+        emit!(ServiceAccountChangedEvent {
+            mint: ctx.accounts.hook_config.mint,
+            owner: ctx.accounts.service_account.owner,
+            registered: false,
+        });
+
+        Ok(())
+    }
+
+    /// Transfer-hook `Execute`, invoked by Token-2022 on every transfer
+    ///
+    /// Rejects transfers from or to a denied owner. In service-only mode
+    /// the destination must be owned by the WALE treasury PDA or a
+    /// registered service account.
+    pub fn transfer_hook(
+        ctx: Context<TransferHook>,
+        _amount: u64,
+    ) -> Result<()> {
+        check_is_transferring(&ctx.accounts.source_token.to_account_info())?;
+
+        require!(!is_listed(&ctx.accounts.source_deny_entry), ErrorCode::WalletDenied);
+        require!(!is_listed(&ctx.accounts.destination_deny_entry), ErrorCode::WalletDenied);
+
+        if ctx.accounts.hook_config.service_only {
+            let destination_owner = ctx.accounts.destination_token.owner;
+            let (treasury, _) = Pubkey::find_program_address(
+                &[b"treasury", ctx.accounts.mint.key().as_ref()],
+                &wale_token::ID,
+            );
+            require!(
+                destination_owner == treasury || is_listed(&ctx.accounts.destination_service_account),
+                ErrorCode::ServiceOnly
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Route the interface's `Execute` instruction, which doesn't use an
+    /// Anchor discriminator, to `transfer_hook`
+    pub fn fallback(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        data: &[u8],
+    ) -> Result<()> {
+        match TransferHookInstruction::unpack(data)? {
+            TransferHookInstruction::Execute { amount } => {
+                __private::__global::transfer_hook(program_id, accounts, &amount.to_le_bytes())
+            }
+            _ => Err(ProgramError::InvalidInstructionData.into()),
+        }
+    }
+}
+
+/// Accounts Token-2022 resolves for `Execute`, after the standard source,
+/// mint, destination, owner and meta list at indexes 0-4
+fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    // A token account's owner is the 32 bytes after its mint
+    let source_owner = Seed::AccountData { account_index: 0, data_index: 32, length: 32 };
+    let destination_owner = Seed::AccountData { account_index: 2, data_index: 32, length: 32 };
+    let mint = Seed::AccountKey { index: 1 };
+
+    Ok(vec![
+        // 5: hook config
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"hook_config".to_vec() }, mint.clone()],
+            false,
+            false,
+        )?,
+        // 6: source owner's deny entry
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"deny".to_vec() }, mint.clone(), source_owner],
+            false,
+            false,
+        )?,
+        // 7: destination owner's deny entry
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"deny".to_vec() }, mint.clone(), destination_owner.clone()],
+            false,
+            false,
+        )?,
+        // 8: destination owner's service registration
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"service".to_vec() }, mint, destination_owner],
+            false,
+            false,
+        )?,
+    ])
+}
+
+/// Whether an optional registry PDA has been created
+fn is_listed(account: &AccountInfo) -> bool {
+    account.owner == &crate::ID && !account.data_is_empty()
+}
+
+/// Reject direct calls that aren't part of a Token-2022 transfer
+fn check_is_transferring(source: &AccountInfo) -> Result<()> {
+    let data = source.try_borrow_data()?;
+    let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    let extension = account.get_extension::<hook_extension::TransferHookAccount>()?;
+    require!(bool::from(extension.transferring), ErrorCode::NotTransferring);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeHook<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The mint's transfer-hook authority
+    pub hook_authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = HookConfig::space(),
+        seeds = [b"hook_config", mint.key().as_ref()],
+        bump
+    )]
+    pub hook_config: Account<'info, HookConfig>,
+
+    /// CHECK: Created and written by the instruction in the TLV layout
+    /// Token-2022 expects
+    #[account(
+        mut,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateHookConfig<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"hook_config", hook_config.mint.as_ref()],
+        bump = hook_config.bump,
+        constraint = hook_config.authority == authority.key() @ ErrorCode::Unauthorized,
+    )]
+    pub hook_config: Account<'info, HookConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct DenyWallet<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"hook_config", hook_config.mint.as_ref()],
+        bump = hook_config.bump,
+        constraint = hook_config.authority == authority.key() @ ErrorCode::Unauthorized,
+    )]
+    pub hook_config: Account<'info, HookConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = DenyEntry::space(),
+        seeds = [b"deny", hook_config.mint.as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub deny_entry: Account<'info, DenyEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AllowWallet<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"hook_config", hook_config.mint.as_ref()],
+        bump = hook_config.bump,
+        constraint = hook_config.authority == authority.key() @ ErrorCode::Unauthorized,
+    )]
+    pub hook_config: Account<'info, HookConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"deny", hook_config.mint.as_ref(), deny_entry.wallet.as_ref()],
+        bump = deny_entry.bump,
+        close = rent_receiver,
+    )]
+    pub deny_entry: Account<'info, DenyEntry>,
+
+    /// CHECK: Receives the closed entry's rent
+    #[account(mut)]
+    pub rent_receiver: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct RegisterServiceAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"hook_config", hook_config.mint.as_ref()],
+        bump = hook_config.bump,
+        constraint = hook_config.authority == authority.key() @ ErrorCode::Unauthorized,
+    )]
+    pub hook_config: Account<'info, HookConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = ServiceAccount::space(),
+        seeds = [b"service", hook_config.mint.as_ref(), owner.as_ref()],
+        bump
+    )]
+    pub service_account: Account<'info, ServiceAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveServiceAccount<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"hook_config", hook_config.mint.as_ref()],
+        bump = hook_config.bump,
+        constraint = hook_config.authority == authority.key() @ ErrorCode::Unauthorized,
+    )]
+    pub hook_config: Account<'info, HookConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"service", hook_config.mint.as_ref(), service_account.owner.as_ref()],
+        bump = service_account.bump,
+        close = rent_receiver,
+    )]
+    pub service_account: Account<'info, ServiceAccount>,
+
+    /// CHECK: Receives the closed registration's rent
+    #[account(mut)]
+    pub rent_receiver: UncheckedAccount<'info>,
+}
+
+/// Account order is fixed by the transfer-hook interface
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    #[account(token::mint = mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Source owner or delegate, already verified by Token-2022
+    pub owner: UncheckedAccount<'info>,
+
+    /// CHECK: Meta list PDA, only read by Token-2022
+    #[account(
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"hook_config", mint.key().as_ref()],
+        bump = hook_config.bump,
+    )]
+    pub hook_config: Account<'info, HookConfig>,
+
+    /// CHECK: Deny entry PDA for the source owner, may not exist
+    #[account(
+        seeds = [b"deny", mint.key().as_ref(), source_token.owner.as_ref()],
+        bump
+    )]
+    pub source_deny_entry: UncheckedAccount<'info>,
+
+    /// CHECK: Deny entry PDA for the destination owner, may not exist
+    #[account(
+        seeds = [b"deny", mint.key().as_ref(), destination_token.owner.as_ref()],
+        bump
+    )]
+    pub destination_deny_entry: UncheckedAccount<'info>,
+
+    /// CHECK: Service registration PDA for the destination owner, may not exist
+    #[account(
+        seeds = [b"service", mint.key().as_ref(), destination_token.owner.as_ref()],
+        bump
+    )]
+    pub destination_service_account: UncheckedAccount<'info>,
+}
+
+#[account]
+#[derive(Default)]
+pub struct HookConfig {
+    pub mint: Pubkey,                 // Mint the hook guards
+    pub authority: Pubkey,            // Manages the deny list and mode
+    pub service_only: bool,           // Only allow transfers into service accounts
+    pub deny_count: u64,              // Wallets currently denied
+    pub bump: u8,                     // PDA bump
+}
+
+#[account]
+#[derive(Default)]
+pub struct DenyEntry {
+    pub mint: Pubkey,                 // Mint the entry applies to
+    pub wallet: Pubkey,               // Denied token account owner
+    pub reason_hash: [u8; 32],        // SHA-256 of the off-chain justification
+    pub denied_at: i64,               // Denial timestamp
+    pub bump: u8,                     // PDA bump
+}
+
+#[account]
+#[derive(Default)]
+pub struct ServiceAccount {
+    pub mint: Pubkey,                 // Mint the registration applies to
+    pub owner: Pubkey,                // Token account owner allowed to receive
+    pub bump: u8,                     // PDA bump
+}
+
+impl HookConfig {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Mint
+            32 + // Authority
+            1 + // Service only
+            8 + // Deny count
+            1 // Bump
+    }
+}
+
+impl DenyEntry {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Mint
+            32 + // Wallet
+            32 + // Reason hash
+            8 + // Denied at
+            1 // Bump
+    }
+}
+
+impl ServiceAccount {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Mint
+            32 + // Owner
+            1 // Bump
+    }
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Numeric overflow occurred")]
+    NumericOverflow,
+    #[msg("Signer is not the hook authority")]
+    Unauthorized,
+    #[msg("Mint's transfer-hook extension does not point at this program")]
+    HookNotConfigured,
+    #[msg("Hook called outside of a token transfer")]
+    NotTransferring,
+    #[msg("Sender or recipient is on the deny list")]
+    WalletDenied,
+    #[msg("Transfers are limited to service and treasury accounts")]
+    ServiceOnly,
+}
+
+// Events
+#[event]
+pub struct HookConfigUpdatedEvent {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub service_only: bool,
+}
+
+#[event]
+pub struct DenyListChangedEvent {
+    pub mint: Pubkey,
+    pub wallet: Pubkey,
+    pub denied: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ServiceAccountChangedEvent {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub registered: bool,
+}
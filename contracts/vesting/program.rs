@@ -0,0 +1,838 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+
+declare_id!("WALEVest11111111111111111111111111111111111");
+
+#[program]
+pub mod vesting {
+    use super::*;
+    
+    /// Lock `amount` tokens from the grantor into a vesting vault for
+    /// `beneficiary`
+    ///
+    /// `seed` lets a grantor give the same beneficiary several grants.
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn create_vesting<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateVesting<'info>>,
+        seed: u64,
+        amount: u64,
+        schedule: VestingSchedule,
+        revocable: bool,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        schedule.validate()?;
+        
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.grantor_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.grantor.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+        // Transfer-fee mints deliver less than `amount`; grant what arrived
+        ctx.accounts.vault.reload()?;
+        let amount = ctx.accounts.vault.amount;
+        
+        let vesting = &mut ctx.accounts.vesting;
+        vesting.grantor = ctx.accounts.grantor.key();
+        vesting.beneficiary = ctx.accounts.beneficiary.key();
+        vesting.mint = ctx.accounts.mint.key();
+        vesting.vault = ctx.accounts.vault.key();
+        vesting.seed = seed;
+        vesting.total_amount = amount;
+        vesting.claimed_amount = 0;
+        vesting.schedule = schedule;
+        vesting.revocable = revocable;
+        vesting.revoked_at = None;
+        vesting.created_at = Clock::get()?.unix_timestamp;
+        vesting.bumps.vesting = *ctx.bumps.get("vesting").unwrap();
+        vesting.bumps.vault = *ctx.bumps.get("vault").unwrap();
+        
+        emit!(VestingCreatedEvent {
+            vesting: vesting.key(),
+            grantor: vesting.grantor,
+            beneficiary: vesting.beneficiary,
+            mint: vesting.mint,
+            amount,
+            schedule: vesting.schedule.clone(),
+            revocable,
+        });
+        
+        Ok(())
+    }
+    
+    /// Release everything vested so far to the beneficiary
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn claim_vested<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimVested<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let vesting = &ctx.accounts.vesting;
+        
+        let amount = vesting.claimable(now)?;
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        
+        let seeds = &[
+            b"vesting".as_ref(),
+            vesting.grantor.as_ref(),
+            vesting.beneficiary.as_ref(),
+            &vesting.seed.to_le_bytes(),
+            &[vesting.bumps.vesting],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.vault.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.beneficiary_token_account.to_account_info(),
+            authority: vesting.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+        
+        let vesting = &mut ctx.accounts.vesting;
+        vesting.claimed_amount = vesting.claimed_amount.checked_add(amount)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        
+        emit!(VestedClaimedEvent {
+            vesting: vesting.key(),
+            beneficiary: vesting.beneficiary,
+            amount,
+            claimed_amount: vesting.claimed_amount,
+            total_amount: vesting.total_amount,
+        });
+        
+        Ok(())
+    }
+    
+    /// Return the unvested part of a revocable grant to the grantor
+    ///
+    /// Whatever has vested at revocation stays claimable by the
+    /// beneficiary; nothing vests afterwards.
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn revoke_vesting<'info>(
+        ctx: Context<'_, '_, '_, 'info, RevokeVesting<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let vesting = &mut ctx.accounts.vesting;
+        let unvested = vesting.revoke(now)?;
+        
+        if unvested > 0 {
+            let seeds = &[
+                b"vesting".as_ref(),
+                vesting.grantor.as_ref(),
+                vesting.beneficiary.as_ref(),
+                &vesting.seed.to_le_bytes(),
+                &[vesting.bumps.vesting],
+            ];
+            let signer = &[&seeds[..]];
+            let cpi_accounts = token_interface::TransferChecked {
+                from: ctx.accounts.vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.grantor_token_account.to_account_info(),
+                authority: vesting.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            )
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+            transfer_cpi::transfer_checked(cpi_ctx, unvested, ctx.accounts.mint.decimals)?;
+        }
+        
+        emit!(VestingRevokedEvent {
+            vesting: vesting.key(),
+            grantor: vesting.grantor,
+            returned_amount: unvested,
+            vested_amount: vesting.total_amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+    
+    /// Lock tokens, typically LP tokens, until `unlock_at`
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn lock_tokens<'info>(
+        ctx: Context<'_, '_, '_, 'info, LockTokens<'info>>,
+        seed: u64,
+        amount: u64,
+        unlock_at: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(unlock_at > now, ErrorCode::InvalidUnlockTime);
+        
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.owner_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+        // Transfer-fee mints deliver less than `amount`; lock what arrived
+        ctx.accounts.vault.reload()?;
+        let amount = ctx.accounts.vault.amount;
+        
+        let lock = &mut ctx.accounts.lock;
+        lock.owner = ctx.accounts.owner.key();
+        lock.mint = ctx.accounts.mint.key();
+        lock.vault = ctx.accounts.vault.key();
+        lock.seed = seed;
+        lock.amount = amount;
+        lock.unlock_at = unlock_at;
+        lock.created_at = now;
+        lock.bumps.lock = *ctx.bumps.get("lock").unwrap();
+        lock.bumps.vault = *ctx.bumps.get("vault").unwrap();
+        
+        emit!(TokensLockedEvent {
+            lock: lock.key(),
+            owner: lock.owner,
+            mint: lock.mint,
+            amount,
+            unlock_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Push a lock's unlock time further out; locks can never be shortened
+    pub fn extend_lock(
+        ctx: Context<ExtendLock>,
+        unlock_at: i64,
+    ) -> Result<()> {
+        let lock = &mut ctx.accounts.lock;
+        
+        require!(unlock_at > lock.unlock_at, ErrorCode::InvalidUnlockTime);
+        
+        lock.unlock_at = unlock_at;
+        
+        emit!(TokensLockedEvent {
+            lock: lock.key(),
+            owner: lock.owner,
+            mint: lock.mint,
+            amount: lock.amount,
+            unlock_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Return locked tokens to the owner once the lock has expired and
+    /// close the lock
+    ///
+    /// Transfer-hook accounts for the mint go in `remaining_accounts`, see
+    /// `transfer_cpi::transfer_checked`.
+    pub fn unlock_tokens<'info>(
+        ctx: Context<'_, '_, '_, 'info, UnlockTokens<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let lock = &ctx.accounts.lock;
+        
+        require!(now >= lock.unlock_at, ErrorCode::StillLocked);
+        
+        let seeds = &[
+            b"token_lock".as_ref(),
+            lock.owner.as_ref(),
+            lock.mint.as_ref(),
+            &lock.seed.to_le_bytes(),
+            &[lock.bumps.lock],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.vault.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.owner_token_account.to_account_info(),
+            authority: lock.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        // Send the whole balance, not just `lock.amount`, so tokens sent to
+        // the vault directly can't block the close
+        let amount = ctx.accounts.vault.amount;
+        transfer_cpi::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+        
+        let cpi_accounts = token_interface::CloseAccount {
+            account: ctx.accounts.vault.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: lock.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        token_interface::close_account(cpi_ctx)?;
+        
+        emit!(TokensUnlockedEvent {
+            lock: lock.key(),
+            owner: lock.owner,
+            mint: lock.mint,
+            amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct CreateVesting<'info> {
+    #[account(mut)]
+    pub grantor: Signer<'info>,
+    
+    /// CHECK: Only recorded as the grant's beneficiary
+    pub beneficiary: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = grantor,
+        space = Vesting::space(),
+        seeds = [
+            b"vesting",
+            grantor.key().as_ref(),
+            beneficiary.key().as_ref(),
+            &seed.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub vesting: Account<'info, Vesting>,
+    
+    #[account(
+        init,
+        payer = grantor,
+        seeds = [b"vesting_vault", vesting.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vesting,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = grantor_token_account.owner == grantor.key(),
+        constraint = grantor_token_account.mint == mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub grantor_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    pub beneficiary: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"vesting",
+            vesting.grantor.as_ref(),
+            beneficiary.key().as_ref(),
+            &vesting.seed.to_le_bytes(),
+        ],
+        bump = vesting.bumps.vesting,
+    )]
+    pub vesting: Account<'info, Vesting>,
+    
+    #[account(mut, address = vesting.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = vesting.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = beneficiary_token_account.mint == vesting.mint @ ErrorCode::InvalidMint,
+    )]
+    pub beneficiary_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeVesting<'info> {
+    pub grantor: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"vesting",
+            grantor.key().as_ref(),
+            vesting.beneficiary.as_ref(),
+            &vesting.seed.to_le_bytes(),
+        ],
+        bump = vesting.bumps.vesting,
+    )]
+    pub vesting: Account<'info, Vesting>,
+    
+    #[account(mut, address = vesting.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = vesting.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = grantor_token_account.mint == vesting.mint @ ErrorCode::InvalidMint,
+    )]
+    pub grantor_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct LockTokens<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = TokenLock::space(),
+        seeds = [
+            b"token_lock",
+            owner.key().as_ref(),
+            mint.key().as_ref(),
+            &seed.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub lock: Account<'info, TokenLock>,
+    
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"lock_vault", lock.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = lock,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key(),
+        constraint = owner_token_account.mint == mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendLock<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"token_lock",
+            owner.key().as_ref(),
+            lock.mint.as_ref(),
+            &lock.seed.to_le_bytes(),
+        ],
+        bump = lock.bumps.lock,
+    )]
+    pub lock: Account<'info, TokenLock>,
+}
+
+#[derive(Accounts)]
+pub struct UnlockTokens<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"token_lock",
+            owner.key().as_ref(),
+            lock.mint.as_ref(),
+            &lock.seed.to_le_bytes(),
+        ],
+        bump = lock.bumps.lock,
+        close = owner,
+    )]
+    pub lock: Account<'info, TokenLock>,
+    
+    #[account(mut, address = lock.vault)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = lock.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = owner_token_account.mint == lock.mint @ ErrorCode::InvalidMint,
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// How a grant unlocks between its cliff and end
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VestingKind {
+    /// Continuous release proportional to elapsed time
+    #[default]
+    Linear,
+    /// Equal tranches released every `period` seconds from `start_ts`
+    Stepped { period: i64 },
+}
+
+
+/// Nothing vests before `cliff_ts`; everything has vested at `end_ts`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct VestingSchedule {
+    pub start_ts: i64,
+    pub cliff_ts: i64,
+    pub end_ts: i64,
+    pub kind: VestingKind,
+}
+
+impl VestingSchedule {
+    pub fn space() -> usize {
+        8 + // Start
+            8 + // Cliff
+            8 + // End
+            1 + 8 // Kind
+    }
+    
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.start_ts <= self.cliff_ts && self.cliff_ts <= self.end_ts && self.start_ts < self.end_ts,
+            ErrorCode::InvalidSchedule
+        );
+        if let VestingKind::Stepped { period } = self.kind {
+            require!(
+                period > 0 && period <= self.end_ts - self.start_ts,
+                ErrorCode::InvalidSchedule
+            );
+        }
+        Ok(())
+    }
+    
+    /// Portion of `total` vested at `now`, never more than `total`
+    pub fn vested(&self, total: u64, now: i64) -> Result<u64> {
+        if now < self.cliff_ts {
+            return Ok(0);
+        }
+        if now >= self.end_ts {
+            return Ok(total);
+        }
+        
+        let elapsed = (now - self.start_ts) as u128;
+        let duration = (self.end_ts - self.start_ts) as u128;
+        let vested = match self.kind {
+            VestingKind::Linear => u128::from(total) * elapsed / duration,
+            VestingKind::Stepped { period } => {
+                let period = period as u128;
+                let steps = duration.div_ceil(period);
+                u128::from(total) * (elapsed / period) / steps
+            }
+        };
+        
+        let vested = u64::try_from(vested).map_err(|_| error!(ErrorCode::NumericOverflow))?;
+        Ok(vested.min(total))
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct Vesting {
+    pub grantor: Pubkey,              // Account that funded the grant
+    pub beneficiary: Pubkey,          // Account allowed to claim
+    pub mint: Pubkey,                 // Mint of the vested tokens
+    pub vault: Pubkey,                // PDA token account holding the grant
+    pub seed: u64,                    // Distinguishes grants to one beneficiary
+    pub total_amount: u64,            // Tokens granted, cut to the vested amount on revocation
+    pub claimed_amount: u64,          // Tokens released so far
+    pub schedule: VestingSchedule,    // Release schedule
+    pub revocable: bool,              // Whether the grantor may revoke
+    pub revoked_at: Option<i64>,      // Revocation timestamp
+    pub created_at: i64,              // Creation timestamp
+    pub bumps: VestingBumps,          // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct VestingBumps {
+    pub vesting: u8,
+    pub vault: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct TokenLock {
+    pub owner: Pubkey,                // Account that locked and may unlock
+    pub mint: Pubkey,                 // Mint of the locked tokens
+    pub vault: Pubkey,                // PDA token account holding the tokens
+    pub seed: u64,                    // Distinguishes locks of one mint
+    pub amount: u64,                  // Tokens locked
+    pub unlock_at: i64,               // Earliest unlock time
+    pub created_at: i64,              // Creation timestamp
+    pub bumps: TokenLockBumps,        // PDA bumps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct TokenLockBumps {
+    pub lock: u8,
+    pub vault: u8,
+}
+
+impl Vesting {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Grantor
+            32 + // Beneficiary
+            32 + // Mint
+            32 + // Vault
+            8 + // Seed
+            8 + // Total amount
+            8 + // Claimed amount
+            VestingSchedule::space() + // Schedule
+            1 + // Revocable
+            1 + 8 + // Revoked at
+            8 + // Created at
+            2 // Bumps
+    }
+    
+    /// Vested tokens at `now`; a revoked grant is fully vested at its
+    /// reduced total
+    pub fn vested_amount(&self, now: i64) -> Result<u64> {
+        if self.revoked_at.is_some() {
+            return Ok(self.total_amount);
+        }
+        self.schedule.vested(self.total_amount, now)
+    }
+    
+    pub fn claimable(&self, now: i64) -> Result<u64> {
+        self.vested_amount(now)?
+            .checked_sub(self.claimed_amount)
+            .ok_or_else(|| error!(ErrorCode::NumericOverflow))
+    }
+    
+    /// Cut the grant to what has vested at `now` and return the unvested
+    /// remainder owed back to the grantor
+    pub fn revoke(&mut self, now: i64) -> Result<u64> {
+        require!(self.revocable, ErrorCode::NotRevocable);
+        require!(self.revoked_at.is_none(), ErrorCode::AlreadyRevoked);
+        
+        let vested = self.vested_amount(now)?;
+        let unvested = self.total_amount.checked_sub(vested)
+            .ok_or(ErrorCode::NumericOverflow)?;
+        self.total_amount = vested;
+        self.revoked_at = Some(now);
+        Ok(unvested)
+    }
+}
+
+impl TokenLock {
+    pub fn space() -> usize {
+        8 + // Discriminator
+            32 + // Owner
+            32 + // Mint
+            32 + // Vault
+            8 + // Seed
+            8 + // Amount
+            8 + // Unlock at
+            8 + // Created at
+            2 // Bumps
+    }
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount must be positive")]
+    InvalidAmount,
+    #[msg("Token account mint does not match")]
+    InvalidMint,
+    #[msg("Numeric overflow occurred")]
+    NumericOverflow,
+    #[msg("Schedule must satisfy start <= cliff <= end with a step no longer than the schedule")]
+    InvalidSchedule,
+    #[msg("Nothing has vested since the last claim")]
+    NothingToClaim,
+    #[msg("Grant is not revocable")]
+    NotRevocable,
+    #[msg("Grant has already been revoked")]
+    AlreadyRevoked,
+    #[msg("Unlock time must be in the future and later than the current one")]
+    InvalidUnlockTime,
+    #[msg("Tokens are still locked")]
+    StillLocked,
+}
+
+// Events
+#[event]
+pub struct VestingCreatedEvent {
+    pub vesting: Pubkey,
+    pub grantor: Pubkey,
+    pub beneficiary: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub schedule: VestingSchedule,
+    pub revocable: bool,
+}
+
+#[event]
+pub struct VestedClaimedEvent {
+    pub vesting: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub claimed_amount: u64,
+    pub total_amount: u64,
+}
+
+#[event]
+pub struct VestingRevokedEvent {
+    pub vesting: Pubkey,
+    pub grantor: Pubkey,
+    pub returned_amount: u64,
+    pub vested_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensLockedEvent {
+    pub lock: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub unlock_at: i64,
+}
+
+#[event]
+pub struct TokensUnlockedEvent {
+    pub lock: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOTAL: u64 = 1_000_003;
+
+    fn schedules() -> Vec<VestingSchedule> {
+        vec![
+            VestingSchedule { start_ts: 100, cliff_ts: 100, end_ts: 1_100, kind: VestingKind::Linear },
+            VestingSchedule { start_ts: 100, cliff_ts: 400, end_ts: 1_100, kind: VestingKind::Linear },
+            // 1,000 seconds in steps of 300 leaves a partial last step
+            VestingSchedule { start_ts: 100, cliff_ts: 100, end_ts: 1_100, kind: VestingKind::Stepped { period: 300 } },
+            VestingSchedule { start_ts: 100, cliff_ts: 400, end_ts: 1_100, kind: VestingKind::Stepped { period: 300 } },
+            VestingSchedule { start_ts: 100, cliff_ts: 1_100, end_ts: 1_100, kind: VestingKind::Stepped { period: 1_000 } },
+        ]
+    }
+
+    #[test]
+    fn vested_never_decreases_or_exceeds_total() {
+        for schedule in schedules() {
+            schedule.validate().unwrap();
+            let mut previous = 0;
+            for now in 0..1_300 {
+                let vested = schedule.vested(TOTAL, now).unwrap();
+                assert!(vested >= previous, "{now}: {vested} < {previous}");
+                assert!(vested <= TOTAL);
+                if now < schedule.cliff_ts {
+                    assert_eq!(vested, 0);
+                }
+                if now >= schedule.end_ts {
+                    assert_eq!(vested, TOTAL);
+                }
+                previous = vested;
+            }
+        }
+        
+        assert_eq!(schedules()[0].vested(u64::MAX, 1_099).unwrap(), (u128::from(u64::MAX) * 999 / 1_000) as u64);
+    }
+
+    #[test]
+    fn stepped_vesting_releases_equal_tranches_then_the_rest() {
+        let schedule = &schedules()[2];
+        // Four tranches: at 400, 700, 1,000 and the partial one ending at 1,100
+        assert_eq!(schedule.vested(TOTAL, 399).unwrap(), 0);
+        assert_eq!(schedule.vested(TOTAL, 400).unwrap(), TOTAL / 4);
+        assert_eq!(schedule.vested(TOTAL, 700).unwrap(), TOTAL / 2);
+        assert_eq!(schedule.vested(TOTAL, 1_000).unwrap(), TOTAL * 3 / 4);
+        assert_eq!(schedule.vested(TOTAL, 1_099).unwrap(), TOTAL * 3 / 4);
+        assert_eq!(schedule.vested(TOTAL, 1_100).unwrap(), TOTAL);
+    }
+
+    #[test]
+    fn revocation_keeps_claims_within_the_vested_amount() {
+        for schedule in schedules() {
+            for revoke_at in (0..1_300).step_by(37) {
+                let mut vesting = Vesting {
+                    total_amount: TOTAL,
+                    schedule: schedule.clone(),
+                    revocable: true,
+                    ..Default::default()
+                };
+                // Claim along the way up to the revocation
+                for now in (0..revoke_at).step_by(91) {
+                    vesting.claimed_amount += vesting.claimable(now).unwrap();
+                }
+                
+                let vested = schedule.vested(TOTAL, revoke_at).unwrap();
+                let claimed = vesting.claimed_amount;
+                let returned = vesting.revoke(revoke_at).unwrap();
+                assert_eq!(returned + vesting.total_amount, TOTAL);
+                assert_eq!(vesting.total_amount, vested);
+                assert!(vesting.revoke(revoke_at).is_err());
+                
+                // What is left to claim never changes after revocation
+                for now in [revoke_at, revoke_at + 1, 1_100, i64::MAX] {
+                    assert_eq!(vesting.claimable(now).unwrap(), vested - claimed);
+                }
+                vesting.claimed_amount += vesting.claimable(revoke_at).unwrap();
+                assert_eq!(vesting.claimed_amount + returned, TOTAL);
+                assert_eq!(vesting.claimable(i64::MAX).unwrap(), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn irrevocable_grants_cannot_be_revoked() {
+        let mut vesting = Vesting {
+            total_amount: TOTAL,
+            schedule: schedules()[0].clone(),
+            ..Default::default()
+        };
+        assert!(vesting.revoke(500).is_err());
+        assert_eq!(vesting.total_amount, TOTAL);
+        assert!(vesting.revoked_at.is_none());
+    }
+}
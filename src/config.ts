@@ -30,7 +30,7 @@ const defaultConfig = {
   // Solana configuration
   solana: {
     endpoint: 'https://api.devnet.solana.com',
-    programId: 'MCPv1111111111111111111111111111111111111',
+    programId: 'MCPv111111111111111111111111111111111111111',
     commitment: 'confirmed',
   },
   
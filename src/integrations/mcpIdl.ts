@@ -0,0 +1,124 @@
+/**
+ * Account layouts of the MCP program used by the connector
+ * Mirrors `TemplateAccount` and `TemplateIndex` in contracts/mcp/program.rs
+ */
+
+import { Idl } from '@project-serum/anchor';
+
+export const MCP_IDL: Idl = {
+  version: '0.1.0',
+  name: 'mcp',
+  instructions: [],
+  accounts: [
+    {
+      name: 'TemplateAccount',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'templateId', type: 'string' },
+          { name: 'templateName', type: 'string' },
+          { name: 'templateVersion', type: 'string' },
+          { name: 'category', type: 'publicKey' },
+          { name: 'tags', type: { vec: 'string' } },
+          { name: 'creator', type: 'publicKey' },
+          { name: 'metadata', type: { defined: 'ContentRef' } },
+          { name: 'createdAt', type: 'i64' },
+          { name: 'updatedAt', type: 'i64' },
+          { name: 'usageCount', type: 'u64' },
+          { name: 'isActive', type: 'bool' },
+          { name: 'pendingOwner', type: { option: 'publicKey' } },
+          { name: 'maintainers', type: { vec: 'publicKey' } },
+          { name: 'revenueSplits', type: { vec: { defined: 'RevenueShare' } } },
+          { name: 'requiresLicense', type: 'bool' },
+          { name: 'ratingCount', type: 'u64' },
+          { name: 'ratingWeightTotal', type: 'u64' },
+          { name: 'ratingScoreTotal', type: 'u64' },
+          { name: 'verified', type: 'bool' },
+          { name: 'auditPolicy', type: { defined: 'AuditPolicy' } },
+          { name: 'updateDelay', type: 'i64' },
+          { name: 'bumps', type: { defined: 'TemplateBumps' } }
+        ]
+      }
+    },
+    {
+      name: 'TemplateIndex',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'kind', type: { defined: 'IndexKind' } },
+          { name: 'key', type: { array: ['u8', 32] } },
+          { name: 'page', type: 'u32' },
+          { name: 'templates', type: { vec: 'publicKey' } },
+          { name: 'bumps', type: { defined: 'IndexBumps' } }
+        ]
+      }
+    }
+  ],
+  types: [
+    {
+      name: 'ContentRef',
+      type: {
+        kind: 'enum',
+        variants: [
+          { name: 'Ipfs', fields: [{ name: 'cid', type: 'bytes' }] },
+          {
+            name: 'Arweave',
+            fields: [
+              { name: 'txId', type: { array: ['u8', 32] } },
+              { name: 'sha256', type: { array: ['u8', 32] } }
+            ]
+          },
+          { name: 'Inline', fields: [{ name: 'data', type: 'bytes' }] },
+          {
+            name: 'Http',
+            fields: [
+              { name: 'url', type: 'string' },
+              { name: 'sha256', type: { array: ['u8', 32] } }
+            ]
+          }
+        ]
+      }
+    },
+    {
+      name: 'RevenueShare',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'recipient', type: 'publicKey' },
+          { name: 'bps', type: 'u16' }
+        ]
+      }
+    },
+    {
+      name: 'AuditPolicy',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'requiredAttestations', type: 'u8' },
+          { name: 'rejectCritical', type: 'bool' }
+        ]
+      }
+    },
+    {
+      name: 'TemplateBumps',
+      type: {
+        kind: 'struct',
+        fields: [{ name: 'template', type: 'u8' }]
+      }
+    },
+    {
+      name: 'IndexKind',
+      type: {
+        kind: 'enum',
+        variants: [{ name: 'Category' }, { name: 'Tag' }, { name: 'Creator' }]
+      }
+    },
+    {
+      name: 'IndexBumps',
+      type: {
+        kind: 'struct',
+        fields: [{ name: 'index', type: 'u8' }]
+      }
+    }
+  ]
+};
@@ -11,9 +11,12 @@ import {
   SystemProgram,
   sendAndConfirmTransaction,
   Keypair,
-  Commitment
+  Commitment,
+  GetProgramAccountsFilter
 } from '@solana/web3.js';
-import { AnchorProvider, Program } from '@project-serum/anchor';
+import { AnchorProvider, BorshAccountsCoder, Program, utils } from '@project-serum/anchor';
+import { createHash } from 'crypto';
+import { MCP_IDL } from './mcpIdl';
 import { TaskTemplate, TaskContext, SolanaTransaction, SolanaAccount } from '../types';
 
 export interface SolanaConnectorConfig {
@@ -22,6 +25,31 @@ export interface SolanaConnectorConfig {
   executionAccountPrefix: string;
 }
 
+export interface TemplateSearchCriteria {
+  id?: string;
+  category?: string;
+  // Accepts a comma separated string as passed through query parameters
+  tags?: string[] | string;
+  creator?: string;
+  activeOnly?: boolean;
+}
+
+// Discovery index kinds, matching the on-chain `IndexKind` enum
+enum IndexKind {
+  Category = 0,
+  Tag = 1,
+  Creator = 2
+}
+
+// Pages fetched per getMultipleAccounts call when walking an index
+const INDEX_PAGE_BATCH = 8;
+
+// Fields of a decoded template account needed by searches
+interface TemplateRecord {
+  templateId: string;
+  isActive: boolean;
+}
+
 /**
  * Connects to Solana blockchain for MCP operations
  */
@@ -29,6 +57,7 @@ export class SolanaConnector {
   private connection: Connection;
   private config: SolanaConnectorConfig;
   private programId: PublicKey;
+  private coder: BorshAccountsCoder;
 
   constructor(
     endpoint: string,
@@ -36,11 +65,12 @@ export class SolanaConnector {
   ) {
     this.connection = new Connection(endpoint, 'confirmed');
     this.config = {
-      programId: config?.programId || 'MCPv1111111111111111111111111111111111111',
+      programId: config?.programId || 'MCPv111111111111111111111111111111111111111',
       templateAccountPrefix: config?.templateAccountPrefix || 'template',
       executionAccountPrefix: config?.executionAccountPrefix || 'execution'
     };
     this.programId = new PublicKey(this.config.programId);
+    this.coder = new BorshAccountsCoder(MCP_IDL);
   }
 
   /**
@@ -124,20 +154,40 @@ export class SolanaConnector {
   }
 
   /**
-   * Search for templates matching criteria
-   * @param criteria Search criteria
+   * Search for templates using the on-chain discovery indexes
+   * @param criteria Template ID, or category slug, tags and/or creator to intersect
    * @returns Array of template IDs
    */
-  async searchTemplates(criteria: Record<string, any>): Promise<string[]> {
-    // TODO: Implement actual on-chain search when program is available
-    console.log(`[Mock] Searching templates with criteria:`, criteria);
+  async searchTemplates(criteria: TemplateSearchCriteria): Promise<string[]> {
+    let templates: TemplateRecord[];
+    
+    if (criteria.id) {
+      templates = await this.findTemplatesById(criteria.id, criteria.creator);
+    } else {
+      const indexes: Array<[IndexKind, Buffer]> = [];
+      
+      if (criteria.category) {
+        const [category] = PublicKey.findProgramAddressSync(
+          [Buffer.from('category'), Buffer.from(criteria.category)],
+          this.programId
+        );
+        indexes.push([IndexKind.Category, category.toBuffer()]);
+      }
+      for (const tag of this.normaliseTags(criteria.tags)) {
+        indexes.push([IndexKind.Tag, createHash('sha256').update(tag).digest()]);
+      }
+      if (criteria.creator) {
+        indexes.push([IndexKind.Creator, new PublicKey(criteria.creator).toBuffer()]);
+      }
+      
+      templates = indexes.length === 0
+        ? await this.fetchAllTemplates()
+        : await this.fetchTemplates(await this.intersectIndexes(indexes));
+    }
     
-    // Return mock template IDs
-    return [
-      'template_defi_01',
-      'template_dao_01',
-      'template_analytics_01'
-    ];
+    return templates
+      .filter(template => !criteria.activeOnly || template.isActive)
+      .map(template => template.templateId);
   }
 
   /**
@@ -223,7 +273,134 @@ export class SolanaConnector {
     };
   }
 
+  /**
+   * Derive the template PDA, matching `template_address` in the program
+   * @param templateId Readable template ID (up to 64 bytes)
+   * @param creator Creator public key
+   * @returns Template account address
+   */
+  deriveTemplateAddress(templateId: string, creator: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from(this.config.templateAccountPrefix), this.idSeed(templateId), creator.toBuffer()],
+      this.programId
+    )[0];
+  }
+
+  /**
+   * Derive the execution PDA, matching `execution_address` in the program
+   * @param taskId Readable task ID (up to 64 bytes)
+   * @param user User public key
+   * @returns Execution account address
+   */
+  deriveExecutionAddress(taskId: string, user: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from(this.config.executionAccountPrefix), this.idSeed(taskId), user.toBuffer()],
+      this.programId
+    )[0];
+  }
+
   // Helper methods
+  private idSeed(id: string): Buffer {
+    // Seeds are capped at 32 bytes, so IDs are hashed before use
+    return createHash('sha256').update(id).digest();
+  }
+  
+  private normaliseTags(tags?: string[] | string): string[] {
+    // Query strings arrive as a single comma separated value
+    const list = typeof tags === 'string' ? tags.split(',') : tags || [];
+    return list.map(tag => tag.trim()).filter(tag => tag.length > 0);
+  }
+  
+  private async intersectIndexes(indexes: Array<[IndexKind, Buffer]>): Promise<string[]> {
+    let matches: Set<string> | null = null;
+    for (const [kind, key] of indexes) {
+      const entries = new Set(await this.readIndex(kind, key));
+      matches = matches === null
+        ? entries
+        : new Set([...matches].filter(template => entries.has(template)));
+    }
+    return [...(matches || [])];
+  }
+  
+  private async readIndex(kind: IndexKind, key: Buffer): Promise<string[]> {
+    const templates: string[] = [];
+    
+    for (let start = 0; ; start += INDEX_PAGE_BATCH) {
+      const pages = Array.from({ length: INDEX_PAGE_BATCH }, (_, i) => {
+        const page = Buffer.alloc(4);
+        page.writeUInt32LE(start + i);
+        return PublicKey.findProgramAddressSync(
+          [Buffer.from('index'), Buffer.from([kind]), key, page],
+          this.programId
+        )[0];
+      });
+      
+      const accountInfos = await this.connection.getMultipleAccountsInfo(pages);
+      for (const info of accountInfos) {
+        if (!info) return templates;
+        
+        const index = this.coder.decode('TemplateIndex', info.data);
+        for (const template of index.templates as PublicKey[]) {
+          templates.push(template.toBase58());
+        }
+      }
+    }
+  }
+  
+  private async fetchTemplates(addresses: string[]): Promise<TemplateRecord[]> {
+    if (addresses.length === 0) return [];
+    
+    const accountInfos = await this.connection.getMultipleAccountsInfo(
+      addresses.map(address => new PublicKey(address))
+    );
+    return accountInfos
+      .filter((info): info is NonNullable<typeof info> => info !== null)
+      .map(info => this.decodeTemplate(info.data));
+  }
+  
+  private async fetchAllTemplates(): Promise<TemplateRecord[]> {
+    const accounts = await this.connection.getProgramAccounts(this.programId, {
+      filters: [this.templateDiscriminatorFilter()]
+    });
+    return accounts.map(({ account }) => this.decodeTemplate(account.data));
+  }
+  
+  private async findTemplatesById(templateId: string, creator?: string): Promise<TemplateRecord[]> {
+    if (creator) {
+      const address = this.deriveTemplateAddress(templateId, new PublicKey(creator));
+      return this.fetchTemplates([address.toBase58()]);
+    }
+    
+    // Without the creator the PDA is unknown, so match the Borsh encoded
+    // `template_id` (length prefix + bytes) right after the discriminator
+    const id = Buffer.from(templateId);
+    const encodedId = Buffer.alloc(4 + id.length);
+    encodedId.writeUInt32LE(id.length);
+    id.copy(encodedId, 4);
+    
+    const accounts = await this.connection.getProgramAccounts(this.programId, {
+      filters: [
+        this.templateDiscriminatorFilter(),
+        { memcmp: { offset: 8, bytes: utils.bytes.bs58.encode(encodedId) } }
+      ]
+    });
+    return accounts.map(({ account }) => this.decodeTemplate(account.data));
+  }
+  
+  private templateDiscriminatorFilter(): GetProgramAccountsFilter {
+    return {
+      memcmp: {
+        offset: 0,
+        bytes: utils.bytes.bs58.encode(BorshAccountsCoder.accountDiscriminator('TemplateAccount'))
+      }
+    };
+  }
+  
+  private decodeTemplate(data: Buffer): TemplateRecord {
+    const template = this.coder.decode('TemplateAccount', data);
+    return { templateId: template.templateId, isActive: template.isActive };
+  }
+  
   private generateTemplateId(template: TaskTemplate, creatorId: string): string {
     const timestamp = new Date().getTime();
     const baseString = `${template.name}_${template.version}_${creatorId}_${timestamp}`;